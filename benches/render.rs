@@ -0,0 +1,63 @@
+//! compares playing a synth one `get_sample` call at a time with rendering blocks.
+//!
+//! run with `cargo bench --bench render`
+
+use std::{hint::black_box, time::Instant};
+use synth_rt::{
+    synth::{Synth, MAX_BLOCK},
+    BLOCK_SIZE, DEFAULT_SAMPLE_RATE,
+};
+
+/// seconds of audio rendered by each run
+const SECONDS: usize = 10;
+
+/// a synth playing a ten note chord
+fn chord() -> Synth {
+    let mut synth = Synth::default();
+    synth.chorus.turn_power_on(true);
+    synth.reverb.turn_power_on(true);
+
+    for note in [48, 52, 55, 59, 60, 64, 67, 71, 72, 76] {
+        synth.play(note, 100);
+    }
+
+    synth
+}
+
+/// times `run` and prints how much faster than real time it is
+fn bench(name: &str, run: impl FnOnce(&mut Synth)) {
+    let mut synth = chord();
+    let start = Instant::now();
+    run(&mut synth);
+    let elapsed = start.elapsed();
+
+    let samples = SECONDS * DEFAULT_SAMPLE_RATE as usize;
+    println!(
+        "{name:<24} {:>8.1} ns/sample {:>8.1}x real time",
+        elapsed.as_nanos() as f64 / samples as f64,
+        SECONDS as f64 / elapsed.as_secs_f64(),
+    );
+}
+
+fn render_blocks(synth: &mut Synth, block_size: usize) {
+    let mut block = vec![0.0; block_size];
+
+    for _ in 0..SECONDS * DEFAULT_SAMPLE_RATE as usize / block_size {
+        synth.render(&mut block);
+        black_box(&block);
+    }
+}
+
+fn main() {
+    bench("get_sample", |synth| {
+        for _ in 0..SECONDS * DEFAULT_SAMPLE_RATE as usize {
+            black_box(synth.get_sample());
+        }
+    });
+    bench(&format!("render ({BLOCK_SIZE} frames)"), |synth| {
+        render_blocks(synth, BLOCK_SIZE)
+    });
+    bench(&format!("render ({MAX_BLOCK} frames)"), |synth| {
+        render_blocks(synth, MAX_BLOCK)
+    });
+}
@@ -0,0 +1,134 @@
+use crate::patch::Patch;
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// how many presets a bank holds, one for each MIDI program number.
+pub const BANK_SIZE: usize = 128;
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Preset {
+    pub name: String,
+    pub patch: Patch,
+}
+
+/// a bank of presets kept on disk as one TOML file per program number, (`000.toml` - `127.toml`)
+#[derive(Clone, Debug)]
+pub struct Bank {
+    pub dir: PathBuf,
+    presets: Vec<Option<Preset>>,
+    /// the selected program number
+    pub current: usize,
+}
+
+impl Bank {
+    /// loads every preset found in `dir`. missing or broken preset files leave their slot empty.
+    pub fn load(dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+
+        let presets = (0..BANK_SIZE)
+            .map(|program| {
+                let path = Self::preset_path(&dir, program);
+
+                if !path.exists() {
+                    return None;
+                }
+
+                match fs::read_to_string(&path)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|preset| Ok(toml::from_str(&preset)?))
+                {
+                    Ok(preset) => Some(preset),
+                    Err(e) => {
+                        println!("[ERROR] => couldn't load preset {}: {e}", path.display());
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        Self {
+            dir,
+            presets,
+            current: 0,
+        }
+    }
+
+    /// where the preset bank lives. (`~/.config/synth-rt/presets/` on linux)
+    pub fn default_dir() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("synth-rt").join("presets"))
+    }
+
+    /// loads the bank from `default_dir`, or an empty bank if there's no config directory.
+    pub fn load_default() -> Self {
+        Self::load(Self::default_dir().unwrap_or_else(|| PathBuf::from("presets")))
+    }
+
+    fn preset_path(dir: &Path, program: usize) -> PathBuf {
+        dir.join(format!("{program:03}.toml"))
+    }
+
+    pub fn get(&self, program: usize) -> Option<&Preset> {
+        self.presets.get(program)?.as_ref()
+    }
+
+    /// the preset in the selected slot
+    pub fn current(&self) -> Option<&Preset> {
+        self.get(self.current)
+    }
+
+    /// selects a program number and returns its preset, if that slot isn't empty
+    pub fn select(&mut self, program: usize) -> Option<&Preset> {
+        self.current = program % BANK_SIZE;
+        self.current()
+    }
+
+    pub fn select_next(&mut self) -> Option<&Preset> {
+        self.select(self.current + 1)
+    }
+
+    pub fn select_prev(&mut self) -> Option<&Preset> {
+        self.select(self.current + BANK_SIZE - 1)
+    }
+
+    /// selects `program` on a MIDI program change, returns the patch to load. empty slots leave
+    /// the sound alone.
+    pub fn program_change(&mut self, program: u8) -> Option<Patch> {
+        let Some(preset) = self.select(program as usize) else {
+            println!("program change => {program}: empty preset slot");
+            return None;
+        };
+
+        println!("program change => {program}: {}", preset.name);
+        Some(preset.patch.clone())
+    }
+
+    /// stores `patch` in the selected slot under `name` and writes it to disk
+    pub fn save_as(&mut self, name: impl Into<String>, patch: Patch) -> Result<()> {
+        let preset = Preset {
+            name: name.into(),
+            patch,
+        };
+
+        fs::create_dir_all(&self.dir)?;
+        fs::write(
+            Self::preset_path(&self.dir, self.current),
+            toml::to_string_pretty(&preset)?,
+        )?;
+        self.presets[self.current] = Some(preset);
+
+        Ok(())
+    }
+
+    /// renames the preset in the selected slot
+    pub fn rename(&mut self, name: impl Into<String>) -> Result<()> {
+        let Some(preset) = self.current().cloned() else {
+            bail!("preset slot {} is empty", self.current);
+        };
+
+        self.save_as(name, preset.patch)
+    }
+}
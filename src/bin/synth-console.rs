@@ -1,25 +1,28 @@
-use anyhow::{bail, Result};
-use midi_control::{ControlEvent, KeyEvent, MidiMessage};
+use anyhow::Result;
 use midir::{Ignore, MidiInput};
 use rodio::OutputStream;
-use serialport;
 use std::{
-    i16,
-    io::{BufRead, BufReader},
     process::exit,
     sync::{Arc, Mutex},
-    thread::spawn,
+    thread::{sleep, spawn},
     time::Duration,
 };
-use synth_rt::{synth::Synth, Player};
+use synth_rt::{
+    bank::Bank, event::SynthHandle, midi::MidiRouter, patch::Patch, synth::Synth,
+    wav_table::WavTable, Player,
+};
 
 fn main() -> Result<()> {
-    // build synth in arc mutex
-    let synth = Arc::new(Mutex::new(Synth::new()));
+    let mut synth = Synth::new(Player::device_sample_rate());
+    synth.set_wav_tables(WavTable::load_default());
+
+    match Patch::load_default() {
+        Ok(Some(patch)) => synth.apply_patch(&patch),
+        Ok(None) => {}
+        Err(e) => println!("[ERROR] => couldn't load the saved patch: {e}"),
+    }
 
-    let output = Player {
-        synth: synth.clone(),
-    };
+    let (output, synth) = Player::new(synth);
     let (_stream, stream_handle) = OutputStream::try_default().unwrap();
 
     // start output
@@ -30,10 +33,12 @@ fn main() -> Result<()> {
         }
     });
 
-    run_midi(synth)
+    let bank = Arc::new(Mutex::new(Bank::load_default()));
+
+    run_midi(synth, MidiRouter::load_default(Some(bank)))
 }
 
-fn run_midi(synth: Arc<Mutex<Synth>>) -> Result<()> {
+fn run_midi(synth: SynthHandle, router: MidiRouter) -> Result<()> {
     let mut midi_in = MidiInput::new("midir reading input")?;
     midi_in.ignore(Ignore::None);
 
@@ -60,6 +65,7 @@ fn run_midi(synth: Arc<Mutex<Synth>>) -> Result<()> {
         let mut midi_in = MidiInput::new("midir reading input")?;
         midi_in.ignore(Ignore::None);
         let synth = synth.clone();
+        let router = router.clone();
 
         // _conn_in needs to be a named parameter, because it needs to be kept alive until the end of the scope
         connections.push(midi_in.connect(
@@ -67,53 +73,13 @@ fn run_midi(synth: Arc<Mutex<Synth>>) -> Result<()> {
             "midir-read-input",
             move |_stamp, message, _| {
                 // println!("{}: {:?} (len = {})", stamp, message, message.len());
-                let message = MidiMessage::from(message);
-                // do midi stuff
-
-                match message {
-                    MidiMessage::Invalid => {
-                        println!("midi_cmd_buf => {message:?}");
-                        println!("midi_cmd -> {message:?}");
-                        println!("midi cmd => {:?}", MidiMessage::from(message));
-                        println!("midi command invalid");
-                    }
-                    MidiMessage::NoteOn(_, KeyEvent { key, value }) => {
-                        synth.lock().unwrap().play(key, value)
-                    }
-                    MidiMessage::NoteOff(_, KeyEvent { key, value: _ }) => {
-                        synth.lock().unwrap().stop(key)
-                    }
-                    MidiMessage::PitchBend(_, lsb, msb) => {
-                        let bend = i16::from_le_bytes([lsb, msb]) as f32 / (32_000.0 * 0.5) - 1.0;
-
-                        if bend > 0.026 || bend < -0.026 {
-                            synth.lock().unwrap().bend_all(bend);
-                        } else {
-                            synth.lock().unwrap().unbend();
-                        }
-                    }
-                    MidiMessage::ControlChange(_, ControlEvent { control, value }) => {
-                        let value = value as f32 / 127.0;
-
-                        match control {
-                            70 => synth.lock().unwrap().set_volume(value),
-                            71 => synth.lock().unwrap().set_atk(value),
-                            72 => synth.lock().unwrap().set_decay(value),
-                            73 => synth.lock().unwrap().set_sus(value),
-                            74 => synth.lock().unwrap().set_cutoff(value),
-                            75 => synth.lock().unwrap().set_resonace(value),
-                            76 => synth.lock().unwrap().set_chorus_depth(value),
-                            77 => synth.lock().unwrap().set_chorus_speed(value),
-                            1 => synth.lock().unwrap().set_leslie_speed(value),
-                            _ => {}
-                        }
-                    }
-                    _ => {} // }
-                }
+                router.handle_bytes(&synth, message);
             },
             (),
         ))
     }
 
-    loop {}
+    loop {
+        sleep(Duration::from_secs(1));
+    }
 }
@@ -0,0 +1,96 @@
+use anyhow::{bail, Result};
+use std::{
+    env,
+    ops::RangeInclusive,
+    sync::{Arc, Mutex},
+};
+use synth_rt::{
+    bank::Bank,
+    midi::MidiRouter,
+    patch::Patch,
+    render::{render_midi_file, BitDepth},
+    synth::Synth,
+    wav_table::WavTable,
+    DEFAULT_SAMPLE_RATE,
+};
+
+const USAGE: &str = "usage: synth-render <input.mid> <output.wav> [--bits 16|24|32] [--tail SECONDS] [--rate HZ] [--patch PATCH.toml]";
+
+/// the sample rates a render can be made at, in Hz
+const SAMPLE_RATES: RangeInclusive<u32> = 8_000..=384_000;
+
+fn main() -> Result<()> {
+    let mut args = env::args().skip(1);
+    let mut paths = Vec::with_capacity(2);
+    let mut bit_depth = BitDepth::Int16;
+    let mut tail = 2.0;
+    let mut sample_rate = DEFAULT_SAMPLE_RATE;
+    let mut patch = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bits" => {
+                let Some(bits) = args.next().and_then(|bits| bits.parse().ok()) else {
+                    bail!("--bits needs a number\n{USAGE}");
+                };
+
+                let Some(depth) = BitDepth::from_bits(bits) else {
+                    bail!("{bits} bit WAV files are not supported, use 16, 24 or 32\n{USAGE}");
+                };
+
+                bit_depth = depth;
+            }
+            "--tail" => {
+                let Some(secs) = args.next().and_then(|secs| secs.parse().ok()) else {
+                    bail!("--tail needs a number of seconds\n{USAGE}");
+                };
+
+                tail = secs;
+            }
+            "--rate" => {
+                let Some(rate) = args.next().and_then(|rate| rate.parse().ok()) else {
+                    bail!("--rate needs a sample rate in Hz\n{USAGE}");
+                };
+
+                if !SAMPLE_RATES.contains(&rate) {
+                    bail!(
+                        "{rate} Hz is not a usable sample rate, use {} to {} Hz\n{USAGE}",
+                        SAMPLE_RATES.start(),
+                        SAMPLE_RATES.end()
+                    );
+                }
+
+                sample_rate = rate;
+            }
+            "--patch" => {
+                let Some(path) = args.next() else {
+                    bail!("--patch needs a path to a patch file\n{USAGE}");
+                };
+
+                patch = Some(Patch::load(path)?);
+            }
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return Ok(());
+            }
+            _ => paths.push(arg),
+        }
+    }
+
+    let [midi_path, wav_path] = paths.as_slice() else {
+        bail!("{USAGE}");
+    };
+
+    let mut synth = Synth::new(sample_rate);
+    synth.set_wav_tables(WavTable::load_default());
+
+    if let Some(patch) = patch {
+        synth.apply_patch(&patch);
+    }
+
+    let router = MidiRouter::load_default(Some(Arc::new(Mutex::new(Bank::load_default()))));
+    render_midi_file(&mut synth, &router, midi_path, wav_path, bit_depth, tail)?;
+    println!("rendered {midi_path} => {wav_path}");
+
+    Ok(())
+}
@@ -1,48 +1,190 @@
 use anyhow::{bail, Result};
 use iced::widget::{
-    button, column, radio, row, svg, text, vertical_slider, vertical_space, Column, Row,
+    button, column, pick_list, radio, row, slider, svg, text, text_input, vertical_slider,
+    vertical_space, Column, Row,
 };
-use iced::window::{self, change_mode, events, Id};
+use iced::window::{self, change_mode, events, frames, Id};
 use iced::Alignment::Center;
 use iced::{Element, Length, Padding, Subscription, Task, Theme};
-use midi_control::{ControlEvent, KeyEvent, MidiMessage};
 use rodio::OutputStream;
-use serialport;
 use std::thread::sleep;
 use std::{
-    i16,
     io::{BufRead, BufReader},
-    num::ParseIntError,
     process::exit,
     sync::{Arc, Mutex},
     thread::{spawn, JoinHandle},
     time::Duration,
 };
-use synth_rt::synth::{OscType, WAVE_TABLE_SIZE};
+use synth_rt::bank::Bank;
+use synth_rt::env::{Curve, CurveShape, Envelope, ModTarget, Stage};
+use synth_rt::event::{Event, SynthHandle};
+use synth_rt::midi::{decode_hex, MidiRouter};
+use synth_rt::osc::RatioMode;
+use synth_rt::patch::{EnvelopePatch, Patch};
+use synth_rt::record::Recorder;
+use synth_rt::synth::{OscType, Param, VelocityCurve, WAVE_TABLE_SIZE};
+use synth_rt::voice::{GlideMode, NotePriority, StealPolicy, VoiceMode};
+use synth_rt::wav_table::WavTable;
 use synth_rt::{synth::Synth, Player};
 
 pub struct SynthUI {
-    synth: Arc<Mutex<Synth>>,
+    synth: SynthHandle,
+    /// the synth's settings, as last published by the audio thread
+    state: Arc<Patch>,
     jhs: (JoinHandle<()>, JoinHandle<()>),
     _stream: OutputStream,
+    recorder: Recorder,
+    bank: Arc<Mutex<Bank>>,
+    /// the name typed into the preset browser
+    preset_name: String,
+    router: MidiRouter,
+    /// true while in MIDI learn mode, moving a slider then arms it to be bound to the next CC.
+    learning: bool,
+    /// the overtone ratio being typed in, and which overtone it's for
+    ratio_input: Option<(usize, String)>,
 }
 
 #[derive(Debug, Clone)]
 enum Message {
     SetVolume(f32),
-    OscVolume { osc_num: usize, vol: f32 },
+    OscVolume {
+        osc_num: usize,
+        vol: f32,
+    },
     DetuneOscUp(usize),
     DetuneOscDown(usize),
-    OscTypeUpdate { osc_num: usize, osc_type: OscType },
+    OscTypeUpdate {
+        osc_num: usize,
+        osc_type: OscType,
+    },
     ChorusVolume(f32),
     ChorusSpeed(f32),
+    ChorusWidth(f32),
     ConnectToSerial,
     ReverbGain(f32),
     ReverbDecay(f32),
-    OvertoneVolume { overtone: usize, vol: f64 },
+    OvertoneVolume {
+        overtone: usize,
+        vol: f64,
+    },
+    OvertoneMode(usize),
+    OvertoneStep {
+        overtone: usize,
+        up: bool,
+    },
+    OvertoneRatioInput {
+        overtone: usize,
+        ratio: String,
+    },
+    OvertoneRatioSubmit,
     WindowEvent(Id),
+    CloseRequested(Id),
     ChorusPowerTogle,
     ReverbPowerTogle,
+    RecordToggle,
+    Refresh,
+    PresetPrev,
+    PresetNext,
+    PresetName(String),
+    PresetSaveAs,
+    PresetRename,
+    LearnToggle,
+    VelocityCurve(VelocityCurve),
+    VelocityCutoff(f32),
+    PolyphonyUp,
+    PolyphonyDown,
+    StealPolicy(StealPolicy),
+    VoiceMode(VoiceMode),
+    NotePriority(NotePriority),
+    GlideMode(GlideMode),
+    Glide(f32),
+    TablePosition {
+        osc_num: usize,
+        position: f32,
+    },
+    TablePositionLfo {
+        osc_num: usize,
+        amount: f32,
+    },
+    TablePositionEnv {
+        osc_num: usize,
+        amount: f32,
+    },
+    OscPan {
+        osc_num: usize,
+        pan: f32,
+    },
+    OscSpread {
+        osc_num: usize,
+        spread: f32,
+    },
+    OctaveUp(usize),
+    OctaveDown(usize),
+    FineTune {
+        osc_num: usize,
+        cents: f32,
+    },
+    UnisonUp(usize),
+    UnisonDown(usize),
+    UnisonDetune {
+        osc_num: usize,
+        cents: f32,
+    },
+    UnisonWidth {
+        osc_num: usize,
+        width: f32,
+    },
+    /// one of an envelope's attack, decay, sustain or release sliders
+    EnvParam {
+        param: Param,
+        value: f32,
+    },
+    EnvCurveShape {
+        envelope: Envelope,
+        stage: Stage,
+        shape: CurveShape,
+    },
+    EnvCurveAmount {
+        envelope: Envelope,
+        stage: Stage,
+        amount: f32,
+    },
+    FilterEnvAmount(f32),
+    ModEnvAmount(f32),
+    ModTarget(ModTarget),
+    OscMute(usize),
+    OscSolo(usize),
+}
+
+impl Message {
+    /// the parameter a slider message changes, used to pick what to bind in MIDI learn mode.
+    fn param(&self) -> Option<Param> {
+        match self {
+            Self::SetVolume(_) => Some(Param::Volume),
+            Self::OscVolume { osc_num, vol: _ } => Some(Param::OscVolume(*osc_num)),
+            Self::ChorusVolume(_) => Some(Param::ChorusDepth),
+            Self::ChorusSpeed(_) => Some(Param::ChorusSpeed),
+            Self::ChorusWidth(_) => Some(Param::ChorusWidth),
+            Self::ReverbGain(_) => Some(Param::ReverbGain),
+            Self::ReverbDecay(_) => Some(Param::ReverbDecay),
+            Self::VelocityCutoff(_) => Some(Param::VelocityCutoff),
+            Self::EnvParam { param, value: _ } => Some(*param),
+            Self::FilterEnvAmount(_) => Some(Param::FilterEnvAmount),
+            Self::ModEnvAmount(_) => Some(Param::ModEnvAmount),
+            Self::Glide(_) => Some(Param::Glide),
+            Self::TablePosition {
+                osc_num,
+                position: _,
+            } => Some(Param::TablePosition(*osc_num)),
+            Self::OvertoneVolume { overtone, vol: _ } => Some(Param::OvertoneVolume(*overtone)),
+            Self::FineTune { osc_num, cents: _ } => Some(Param::FineTune(*osc_num)),
+            Self::UnisonDetune { osc_num, cents: _ } => Some(Param::UnisonDetune(*osc_num)),
+            Self::UnisonWidth { osc_num, width: _ } => Some(Param::UnisonWidth(*osc_num)),
+            Self::OscPan { osc_num, pan: _ } => Some(Param::OscPan(*osc_num)),
+            Self::OscSpread { osc_num, spread: _ } => Some(Param::OscSpread(*osc_num)),
+            _ => None,
+        }
+    }
 }
 
 impl SynthUI {
@@ -53,54 +195,275 @@ impl SynthUI {
 
     /// Updated the state of your app
     fn update(&mut self, message: Message) -> Task<Message> {
-        match message {
-            Message::SetVolume(vol) => self.synth.lock().unwrap().set_volume(vol / 100.0),
-            Message::OscVolume { osc_num, vol } => {
-                self.synth.lock().unwrap().osc_type[osc_num].1 = vol / 100.0
+        if self.learning {
+            if let Some(param) = message.param() {
+                self.router.learn(Some(param));
             }
-            Message::DetuneOscUp(osc_num) => {
-                if self.synth.lock().unwrap().osc_s[osc_num].1 < 12 {
-                    self.synth.lock().unwrap().osc_s[osc_num].1 += 1
+        }
+
+        match message {
+            Message::SetVolume(vol) => self.synth.send(Event::Param(Param::Volume, vol / 100.0)),
+            Message::OscVolume { osc_num, vol } => self
+                .synth
+                .send(Event::Param(Param::OscVolume(osc_num), vol / 100.0)),
+            Message::DetuneOscUp(osc_num) => self.synth.edit(move |synth| {
+                if synth.osc_s[osc_num].1 < 12 {
+                    synth.osc_s[osc_num].1 += 1
                 }
-            }
-            Message::DetuneOscDown(osc_num) => {
-                if self.synth.lock().unwrap().osc_s[osc_num].1 > -12 {
-                    self.synth.lock().unwrap().osc_s[osc_num].1 -= 1
+            }),
+            Message::DetuneOscDown(osc_num) => self.synth.edit(move |synth| {
+                if synth.osc_s[osc_num].1 > -12 {
+                    synth.osc_s[osc_num].1 -= 1
                 }
-            }
-            Message::OscTypeUpdate { osc_num, osc_type } => {
-                self.synth.lock().unwrap().osc_type[osc_num].0 = osc_type
-            }
-            Message::ChorusVolume(vol) => self.synth.lock().unwrap().chorus.set_volume(vol / 100.0),
-            Message::ChorusSpeed(speed) => {
-                self.synth.lock().unwrap().chorus.set_speed(speed / 100.0)
-            }
+            }),
+            Message::OscTypeUpdate { osc_num, osc_type } => self
+                .synth
+                .edit(move |synth| synth.osc_type[osc_num].0 = osc_type),
+            Message::ChorusVolume(vol) => self
+                .synth
+                .edit(move |synth| synth.chorus.set_volume(vol / 100.0)),
+            Message::ChorusSpeed(speed) => self
+                .synth
+                .edit(move |synth| synth.chorus.set_speed(speed / 100.0)),
+            Message::ChorusWidth(width) => self
+                .synth
+                .send(Event::Param(Param::ChorusWidth, width / 100.0)),
             Message::ConnectToSerial => {
                 let s = self.synth.clone();
-                self.jhs.1 = spawn(move || con_to_serial(s));
+                let r = self.router.clone();
+                self.jhs.1 = spawn(move || con_to_serial(s, r));
                 sleep(Duration::from_secs_f64(0.5))
             }
             Message::ReverbGain(gain) => {
                 // println!("[ERROR] Reverb is not yet implemented")
-                self.synth.lock().unwrap().reverb.set_gain(gain * 0.01);
+                self.synth
+                    .send(Event::Param(Param::ReverbGain, gain * 0.01));
             }
             Message::ReverbDecay(decay) => {
                 // println!("[ERROR] Reverb is not yet implemented")
-                self.synth.lock().unwrap().reverb.set_decay(decay * 0.01);
+                self.synth
+                    .send(Event::Param(Param::ReverbDecay, decay * 0.01));
             }
-            Message::OvertoneVolume { overtone, vol } => {
-                self.synth.lock().unwrap().overtones[overtone].volume = vol / 100.0;
-                self.synth.lock().unwrap().set_overtones();
+            Message::OvertoneVolume { overtone, vol } => self.synth.send(Event::Param(
+                Param::OvertoneVolume(overtone),
+                (vol / 100.0) as f32,
+            )),
+            Message::OvertoneMode(overtone) => self.synth.edit(move |synth| {
+                let ot = &mut synth.overtones[overtone];
+                ot.mode = ot.mode.next();
+                ot.overtone = ot.mode.snap(ot.overtone);
+                synth.set_overtones();
+            }),
+            Message::OvertoneStep { overtone, up } => self.synth.edit(move |synth| {
+                let ot = &mut synth.overtones[overtone];
+                ot.overtone = ot.mode.step(ot.overtone, up);
+                synth.set_overtones();
+            }),
+            Message::OvertoneRatioInput { overtone, ratio } => {
+                self.ratio_input = Some((overtone, ratio))
+            }
+            Message::OvertoneRatioSubmit => {
+                let Some((overtone, ratio)) = self.ratio_input.take() else {
+                    return Task::none();
+                };
+
+                match ratio.trim().parse::<f64>() {
+                    Ok(ratio) if ratio > 0.0 => self.synth.edit(move |synth| {
+                        let ot = &mut synth.overtones[overtone];
+                        ot.overtone = ot.mode.snap(ratio);
+                        synth.set_overtones();
+                    }),
+                    _ => println!("[ERROR] => \"{ratio}\" is not an overtone ratio"),
+                }
             }
             Message::WindowEvent(id) => return change_mode(id, window::Mode::Fullscreen),
-            Message::ChorusPowerTogle => self.synth.lock().unwrap().chorus.power_toggle(),
-            Message::ReverbPowerTogle => self.synth.lock().unwrap().reverb.power_toggle(),
+            Message::CloseRequested(id) => {
+                if let Err(e) = self.state.save_default() {
+                    println!("[ERROR] => couldn't save the patch: {e}");
+                }
+
+                return window::close(id);
+            }
+            Message::ChorusPowerTogle => self.synth.edit(|synth| synth.chorus.power_toggle()),
+            Message::ReverbPowerTogle => self.synth.edit(|synth| synth.reverb.power_toggle()),
+            Message::RecordToggle => {
+                let res = if self.recorder.is_recording() {
+                    self.recorder.stop()
+                } else {
+                    self.recorder.start()
+                };
+
+                match res {
+                    Ok(file) => println!("recording => {}", file.display()),
+                    Err(e) => println!("[ERROR] => recording error: {e}"),
+                }
+            }
+            // picks up changes made over MIDI, and redraws the elapsed recording time
+            Message::Refresh => {}
+            Message::PresetPrev | Message::PresetNext => {
+                let mut bank = self.bank.lock().unwrap();
+
+                let preset = if let Message::PresetPrev = message {
+                    bank.select_prev()
+                } else {
+                    bank.select_next()
+                };
+
+                if let Some(preset) = preset {
+                    self.synth
+                        .send(Event::Patch(Box::new(preset.patch.clone())));
+                    self.preset_name = preset.name.clone();
+                } else {
+                    self.preset_name.clear();
+                }
+            }
+            Message::PresetName(name) => self.preset_name = name,
+            Message::PresetSaveAs => {
+                let patch = (*self.state).clone();
+
+                if let Err(e) = self
+                    .bank
+                    .lock()
+                    .unwrap()
+                    .save_as(self.preset_name.clone(), patch)
+                {
+                    println!("[ERROR] => couldn't save preset: {e}");
+                }
+            }
+            Message::PresetRename => {
+                if let Err(e) = self.bank.lock().unwrap().rename(self.preset_name.clone()) {
+                    println!("[ERROR] => couldn't rename preset: {e}");
+                }
+            }
+            Message::LearnToggle => {
+                self.learning = !self.learning;
+                self.router.learn(None);
+            }
+            Message::PolyphonyUp | Message::PolyphonyDown => {
+                let up = matches!(message, Message::PolyphonyUp);
+
+                self.synth.edit(move |synth| {
+                    let polyphony = synth.voices.polyphony();
+
+                    synth.set_polyphony(if up { polyphony + 1 } else { polyphony - 1 });
+                });
+            }
+            Message::StealPolicy(policy) => {
+                self.synth.edit(move |synth| synth.voices.policy = policy)
+            }
+            Message::VoiceMode(mode) => self.synth.edit(move |synth| synth.set_voice_mode(mode)),
+            Message::NotePriority(priority) => {
+                self.synth.edit(move |synth| synth.note_priority = priority)
+            }
+            Message::GlideMode(mode) => self.synth.edit(move |synth| synth.glide_mode = mode),
+            Message::Glide(glide) => self.synth.send(Event::Param(Param::Glide, glide / 100.0)),
+            Message::TablePosition { osc_num, position } => self.synth.send(Event::Param(
+                Param::TablePosition(osc_num),
+                position / 100.0,
+            )),
+            Message::TablePositionLfo { osc_num, amount } => self
+                .synth
+                .edit(move |synth| synth.table_position[osc_num].lfo = amount / 100.0),
+            Message::TablePositionEnv { osc_num, amount } => self
+                .synth
+                .edit(move |synth| synth.table_position[osc_num].env = amount / 100.0),
+            Message::OscPan { osc_num, pan } => self.synth.send(Event::Param(
+                Param::OscPan(osc_num),
+                (pan / 100.0 + 1.0) / 2.0,
+            )),
+            Message::OscSpread { osc_num, spread } => self
+                .synth
+                .send(Event::Param(Param::OscSpread(osc_num), spread / 100.0)),
+            Message::OctaveUp(osc_num) => self
+                .synth
+                .edit(move |synth| synth.set_octave(osc_num, synth.octave[osc_num] + 1)),
+            Message::OctaveDown(osc_num) => self
+                .synth
+                .edit(move |synth| synth.set_octave(osc_num, synth.octave[osc_num] - 1)),
+            Message::FineTune { osc_num, cents } => self.synth.send(Event::Param(
+                Param::FineTune(osc_num),
+                (cents / 100.0 + 1.0) / 2.0,
+            )),
+            Message::UnisonUp(osc_num) | Message::UnisonDown(osc_num) => {
+                let up = matches!(message, Message::UnisonUp(_));
+
+                self.synth.edit(move |synth| {
+                    let mut unison = synth.unison[osc_num];
+                    unison.voices = if up {
+                        unison.voices + 1
+                    } else {
+                        unison.voices.saturating_sub(1)
+                    };
+
+                    synth.set_unison(osc_num, unison);
+                });
+            }
+            Message::UnisonDetune { osc_num, cents } => self
+                .synth
+                .send(Event::Param(Param::UnisonDetune(osc_num), cents / 100.0)),
+            Message::UnisonWidth { osc_num, width } => self
+                .synth
+                .send(Event::Param(Param::UnisonWidth(osc_num), width / 100.0)),
+            Message::EnvParam { param, value } => {
+                self.synth.send(Event::Param(param, value / 100.0))
+            }
+            Message::EnvCurveShape {
+                envelope,
+                stage,
+                shape,
+            } => {
+                let curve = Curve {
+                    shape,
+                    ..self.env_curve(envelope, stage)
+                };
+
+                self.synth
+                    .edit(move |synth| synth.set_env_curve(envelope, stage, curve))
+            }
+            Message::EnvCurveAmount {
+                envelope,
+                stage,
+                amount,
+            } => {
+                let curve = Curve {
+                    amount: amount / 100.0,
+                    ..self.env_curve(envelope, stage)
+                };
+
+                self.synth
+                    .edit(move |synth| synth.set_env_curve(envelope, stage, curve))
+            }
+            Message::FilterEnvAmount(amount) => self.synth.send(Event::Param(
+                Param::FilterEnvAmount,
+                (amount / 100.0 + 1.0) / 2.0,
+            )),
+            Message::ModEnvAmount(amount) => self.synth.send(Event::Param(
+                Param::ModEnvAmount,
+                (amount / 100.0 + 1.0) / 2.0,
+            )),
+            Message::ModTarget(target) => {
+                self.synth.edit(move |synth| synth.set_mod_target(target))
+            }
+            Message::OscMute(osc_num) => self
+                .synth
+                .edit(move |synth| synth.set_muted(osc_num, !synth.muted[osc_num])),
+            Message::OscSolo(osc_num) => self
+                .synth
+                .edit(move |synth| synth.set_solo(osc_num, !synth.solo[osc_num])),
+            Message::VelocityCurve(curve) => {
+                self.synth.edit(move |synth| synth.velocity_curve = curve)
+            }
+            Message::VelocityCutoff(amount) => self
+                .synth
+                .send(Event::Param(Param::VelocityCutoff, amount / 100.0)),
         }
 
+        self.state = self.synth.state();
+
         Task::none()
     }
 
-    fn view(&self) -> Element<Message> {
+    fn view(&self) -> Element<'_, Message> {
         if !self.jhs.1.is_finished() {
             self.synth_view()
         } else {
@@ -108,7 +471,7 @@ impl SynthUI {
         }
     }
 
-    fn con_serial_view(&self) -> Element<Message> {
+    fn con_serial_view(&self) -> Element<'_, Message> {
         let con_button = button("Connect").on_press(Message::ConnectToSerial);
 
         column![text!["no serial MIDI connection found. Please connect the arduino and click the button bellow."].size(24), con_button]
@@ -120,27 +483,41 @@ impl SynthUI {
     }
 
     /// the main layout for the app when serial is connected
-    fn synth_view(&self) -> Element<Message> {
+    fn synth_view(&self) -> Element<'_, Message> {
         // println!("view");
         column![
             // row![text!("waveform view").center()]
-            self.waveform_vis()
-                .align_y(Center)
-                .height(Length::FillPortion(30))
-                .width(Length::Fill),
-            // row![
-            //     column![text!("ADSR view").center()]
-            //         .align_x(Center)
-            //         .height(Length::Fill)
-            //         .width(Length::FillPortion(1)),
-            //     column![text!("Low Pass view").center()]
-            //         .align_x(Center)
-            //         .height(Length::Fill)
-            //         .width(Length::FillPortion(1))
-            // ]
-            // .align_y(Center)
-            // .height(20)
-            // .width(Length::Fill),
+            row![
+                self.waveform_vis()
+                    .align_y(Center)
+                    .height(Length::Fill)
+                    .width(Length::FillPortion(800)),
+                self.presets()
+                    .align_x(Center)
+                    .height(Length::Fill)
+                    .width(Length::FillPortion(200)),
+            ]
+            .align_y(Center)
+            .height(Length::FillPortion(20))
+            .width(Length::Fill),
+            row![
+                self.envelope(Envelope::Amp)
+                    .align_x(Center)
+                    .height(Length::Fill)
+                    .width(Length::FillPortion(1)),
+                self.envelope(Envelope::Filter)
+                    .align_x(Center)
+                    .height(Length::Fill)
+                    .width(Length::FillPortion(1)),
+                self.envelope(Envelope::Mod)
+                    .align_x(Center)
+                    .height(Length::Fill)
+                    .width(Length::FillPortion(1)),
+            ]
+            .align_y(Center)
+            .spacing(16)
+            .height(Length::FillPortion(25))
+            .width(Length::Fill),
             row![
                 self.osc(0)
                     .align_x(Center)
@@ -168,9 +545,14 @@ impl SynthUI {
                 .align_x(Center)
                 .height(Length::Fill)
                 .width(Length::FillPortion(200)),
+                self.velocity()
+                    .align_x(Center)
+                    .height(Length::Fill)
+                    .width(Length::FillPortion(100)),
                 column![
                     text!["Vol."].size(24).align_x(Center).width(Length::Fill),
-                    self.vu_meter()
+                    self.vu_meter(),
+                    self.record(),
                 ]
                 // .padding([24, 0])
                 .align_x(Center)
@@ -182,7 +564,7 @@ impl SynthUI {
                 bottom: 24.0,
                 ..Default::default()
             })
-            .height(Length::FillPortion(70))
+            .height(Length::FillPortion(55))
             .width(Length::Fill),
         ]
         .height(Length::Fill)
@@ -193,20 +575,20 @@ impl SynthUI {
     }
 
     fn waveform_vis(&self) -> Row<'_, Message> {
-        let waveforms = self.synth.lock().unwrap().wave_tables.clone();
-        let weights = self.synth.lock().unwrap().osc_type.clone();
+        let waveforms = self.synth.wave_tables.load_full();
+        let weights = self
+            .state
+            .oscillators
+            .iter()
+            .filter_map(|osc| Some((waveforms.osc_type(&osc.wave).ok()?, osc.volume)));
 
         let mut waveform = [0.0; WAVE_TABLE_SIZE];
 
         for (osc_type, weight) in weights {
-            let wf = match osc_type {
-                OscType::Sin => waveforms.sin.clone(),
-                OscType::Tri => waveforms.tri.clone(),
-                OscType::Sqr => waveforms.sqr.clone(),
-                OscType::Saw => waveforms.saw.clone(),
-            };
-
-            wf.iter()
+            waveforms
+                .table(osc_type)
+                .table()
+                .iter()
                 .enumerate()
                 .for_each(|(i, sample)| waveform[i] += (sample * weight) / 3.0)
         }
@@ -233,25 +615,110 @@ impl SynthUI {
         row![svg(handle).width(Length::Fill).height(Length::Fill)]
     }
 
+    fn presets(&self) -> Column<'_, Message> {
+        let bank = self.bank.lock().unwrap();
+        let name = bank
+            .current()
+            .map(|preset| preset.name.clone())
+            .unwrap_or("<empty>".into());
+
+        column![
+            text!["Presets"].size(24),
+            row![
+                button("<").on_press(Message::PresetPrev),
+                text!("{:03}: {name}", bank.current)
+                    .center()
+                    .width(Length::Fill),
+                button(">").on_press(Message::PresetNext),
+            ]
+            .align_y(Center),
+            text_input("preset name", &self.preset_name).on_input(Message::PresetName),
+            row![
+                button("Save As").on_press(Message::PresetSaveAs),
+                button("Rename").on_press(Message::PresetRename),
+            ]
+            .spacing(8),
+            self.voices(),
+            self.mono(),
+            self.midi_learn(),
+        ]
+        .spacing(8)
+        .align_x(Center)
+    }
+
+    fn voices(&self) -> Row<'_, Message> {
+        let voices = self.state.voices;
+
+        row![
+            text!("Voices"),
+            button("-").on_press(Message::PolyphonyDown),
+            text!("{}", voices.polyphony),
+            button("+").on_press(Message::PolyphonyUp),
+            pick_list(
+                StealPolicy::ALL,
+                Some(voices.stealing),
+                Message::StealPolicy
+            ),
+        ]
+        .spacing(8)
+        .align_y(Center)
+    }
+
+    fn mono(&self) -> Column<'_, Message> {
+        let voices = self.state.voices;
+
+        column![
+            row![
+                pick_list(VoiceMode::ALL, Some(voices.mode), Message::VoiceMode),
+                pick_list(
+                    NotePriority::ALL,
+                    Some(voices.priority),
+                    Message::NotePriority
+                ),
+            ]
+            .spacing(8),
+            row![
+                text!("Glide"),
+                slider(0.0..=100.0, voices.glide * 100.0, Message::Glide),
+                pick_list(GlideMode::ALL, Some(voices.glide_mode), Message::GlideMode),
+            ]
+            .spacing(8)
+            .align_y(Center),
+        ]
+        .spacing(8)
+        .align_x(Center)
+    }
+
+    fn midi_learn(&self) -> Column<'_, Message> {
+        let status = match (self.learning, self.router.learning()) {
+            (false, _) => String::new(),
+            (true, None) => "move a slider".into(),
+            (true, Some(param)) => format!("{param:?} <= move a knob"),
+        };
+
+        column![
+            button(if self.learning { "Done" } else { "MIDI Learn" })
+                .on_press(Message::LearnToggle),
+            text!("{status}"),
+        ]
+        .align_x(Center)
+    }
+
     fn reverb(&self) -> Column<'_, Message> {
         let decay = vertical_slider(
             0.0..=100.0,
-            self.synth.lock().unwrap().reverb.decay * 100.0,
+            self.state.reverb.decay * 100.0,
             Message::ReverbDecay,
         );
 
         let gain = vertical_slider(
             0.0..=100.0,
-            self.synth.lock().unwrap().reverb.gain * 100.0,
+            self.state.reverb.gain * 100.0,
             Message::ReverbGain,
         );
 
-        let power = button(if self.synth.lock().unwrap().reverb.power {
-            "On"
-        } else {
-            "Off"
-        })
-        .on_press(Message::ReverbPowerTogle);
+        let power = button(if self.state.reverb.power { "On" } else { "Off" })
+            .on_press(Message::ReverbPowerTogle);
 
         column![
             text!["Reverb"].size(24),
@@ -273,28 +740,197 @@ impl SynthUI {
         ]
         .align_x(Center)
         .height(Length::FillPortion(50))
-        .into()
+    }
+
+    /// the saved settings of one of the envelopes
+    fn env_patch(&self, envelope: Envelope) -> EnvelopePatch {
+        let state = &self.state;
+
+        match envelope {
+            Envelope::Amp => state.envelope,
+            Envelope::Filter => state
+                .filter_envelope
+                .map_or(state.envelope, |env| env.envelope),
+            Envelope::Mod => state
+                .mod_envelope
+                .map_or(state.envelope, |env| env.envelope),
+        }
+    }
+
+    /// the shape of one of an envelope's stages
+    fn env_curve(&self, envelope: Envelope, stage: Stage) -> Curve {
+        let env = self.env_patch(envelope);
+
+        match stage {
+            Stage::Attack => env.attack_curve,
+            Stage::Decay => env.decay_curve,
+            Stage::Release => env.release_curve,
+        }
+    }
+
+    fn envelope(&self, envelope: Envelope) -> Column<'_, Message> {
+        let env = self.env_patch(envelope);
+        let params = match envelope {
+            Envelope::Amp => [Param::Attack, Param::Decay, Param::Sustain, Param::Release],
+            Envelope::Filter => [
+                Param::FilterAttack,
+                Param::FilterDecay,
+                Param::FilterSustain,
+                Param::FilterRelease,
+            ],
+            Envelope::Mod => [
+                Param::ModAttack,
+                Param::ModDecay,
+                Param::ModSustain,
+                Param::ModRelease,
+            ],
+        };
+        let values = [env.attack, env.decay, env.sustain, env.release];
+
+        let mut times = row![].height(Length::Fill);
+
+        for ((param, value), name) in params.into_iter().zip(values).zip(["A", "D", "S", "R"]) {
+            times = times.push(
+                column![
+                    text!("{name}"),
+                    vertical_slider(0.0..=100.0, value * 100.0, move |value| {
+                        Message::EnvParam { param, value }
+                    })
+                ]
+                .align_x(Center)
+                .width(Length::Fill),
+            );
+        }
+
+        // the filter and modulation envelopes can sweep either way
+        let amount = match envelope {
+            Envelope::Amp => None,
+            Envelope::Filter => Some((
+                self.state.filter_envelope.map_or(1.0, |env| env.amount),
+                Message::FilterEnvAmount as fn(f32) -> Message,
+            )),
+            Envelope::Mod => Some((
+                self.state.mod_envelope.map_or(0.0, |env| env.amount),
+                Message::ModEnvAmount as fn(f32) -> Message,
+            )),
+        };
+
+        if let Some((amount, on_change)) = amount {
+            times = times.push(
+                column![
+                    text!["Amt."],
+                    vertical_slider(-100.0..=100.0, amount * 100.0, on_change)
+                ]
+                .align_x(Center)
+                .width(Length::Fill),
+            );
+        }
+
+        let mut curves = column![].spacing(4).align_x(Center);
+
+        if envelope == Envelope::Mod {
+            let target = self.state.mod_envelope.map(|env| env.target);
+            curves = curves.push(pick_list(ModTarget::ALL, target, Message::ModTarget));
+        }
+
+        curves = curves.push(text!["Curves"]);
+
+        for (stage, name) in Stage::ALL.into_iter().zip(["A", "D", "R"]) {
+            let curve = self.env_curve(envelope, stage);
+
+            curves = curves.push(
+                row![
+                    text!("{name}"),
+                    pick_list(CurveShape::ALL, Some(curve.shape), move |shape| {
+                        Message::EnvCurveShape {
+                            envelope,
+                            stage,
+                            shape,
+                        }
+                    }),
+                    slider(0.0..=100.0, curve.amount * 100.0, move |amount| {
+                        Message::EnvCurveAmount {
+                            envelope,
+                            stage,
+                            amount,
+                        }
+                    }),
+                ]
+                .spacing(8)
+                .align_y(Center),
+            );
+        }
+
+        column![
+            text!("{envelope} Env.").size(24),
+            row![
+                times.width(Length::FillPortion(1)),
+                curves.width(Length::FillPortion(1))
+            ]
+            .spacing(8)
+            .height(Length::Fill),
+        ]
+        .spacing(4)
+        .align_x(Center)
+    }
+
+    fn velocity(&self) -> Column<'_, Message> {
+        let selection = Some(self.state.velocity.curve);
+
+        let cutoff = vertical_slider(
+            0.0..=100.0,
+            self.state.velocity.cutoff * 100.0,
+            Message::VelocityCutoff,
+        );
+
+        column![
+            text!["Velocity"].size(24),
+            radio(
+                "Linear",
+                VelocityCurve::Linear,
+                selection,
+                Message::VelocityCurve
+            ),
+            radio(
+                "Exp.",
+                VelocityCurve::Exponential,
+                selection,
+                Message::VelocityCurve
+            ),
+            radio(
+                "Fixed",
+                VelocityCurve::Fixed,
+                selection,
+                Message::VelocityCurve
+            ),
+            text!["Cutoff"],
+            cutoff,
+        ]
+        .spacing(4)
+        .align_x(Center)
     }
 
     fn chorus(&self) -> Column<'_, Message> {
         let volume = vertical_slider(
             0.0..=100.0,
-            self.synth.lock().unwrap().chorus.volume * 100.0,
+            self.state.chorus.volume * 100.0,
             Message::ChorusVolume,
         );
 
         let speed = vertical_slider(
             0.0..=100.0,
-            self.synth.lock().unwrap().chorus.speed * 100.0,
+            self.state.chorus.speed * 100.0,
             Message::ChorusSpeed,
         );
 
-        let power = button(if self.synth.lock().unwrap().chorus.power {
-            "On"
-        } else {
-            "Off"
-        })
-        .on_press(Message::ChorusPowerTogle);
+        let width = vertical_slider(
+            0.0..=100.0,
+            self.state.chorus.width * 100.0,
+            Message::ChorusWidth,
+        );
+
+        let power = button(if self.state.chorus.power { "On" } else { "Off" })
+            .on_press(Message::ChorusPowerTogle);
 
         column![
             text!["Chorus"].size(24),
@@ -305,6 +941,10 @@ impl SynthUI {
                     .height(Length::Fill)
                     .width(Length::Fill),
                 column![text!["Speed"], speed]
+                    .align_x(Center)
+                    .height(Length::Fill)
+                    .width(Length::Fill),
+                column![text!["Width"], width]
                     .align_x(Center)
                     .height(Length::Fill)
                     .width(Length::Fill)
@@ -315,20 +955,19 @@ impl SynthUI {
         ]
         .align_x(Center)
         .height(Length::FillPortion(50))
-        .into()
     }
 
     fn osc(&self, osc_i: usize) -> Column<'_, Message> {
         let volume = vertical_slider(
             0.0..=100.0,
-            self.synth.lock().unwrap().osc_type[osc_i].1 * 100.0,
+            self.state.oscillators[osc_i].volume * 100.0,
             move |vol| Message::OscVolume {
                 osc_num: osc_i,
                 vol,
             },
         );
 
-        let detune_amt = self.synth.lock().unwrap().osc_s[osc_i].1;
+        let detune_amt = self.state.oscillators[osc_i].detune;
 
         let detune = column![
             // detune up
@@ -346,9 +985,10 @@ impl SynthUI {
             // horizontal_space(),
         ];
 
-        let selection = Some(self.synth.lock().unwrap().osc_type[osc_i].0);
+        let tables = self.synth.wave_tables.load_full();
+        let selection = tables.osc_type(&self.state.oscillators[osc_i].wave).ok();
 
-        let waveform = column![
+        let mut waveform = column![
             text!["WaveForm"],
             radio("Sin", OscType::Sin, selection, |osc_type| {
                 Message::OscTypeUpdate {
@@ -376,6 +1016,24 @@ impl SynthUI {
             },),
         ];
 
+        for (id, table) in tables.custom.iter().enumerate() {
+            waveform = waveform.push(radio(
+                table.name.to_string(),
+                OscType::Custom(id),
+                selection,
+                |osc_type| Message::OscTypeUpdate {
+                    osc_num: osc_i,
+                    osc_type,
+                },
+            ));
+        }
+
+        let osc = &self.state.oscillators[osc_i];
+        let mute =
+            button(if osc.muted { "Muted" } else { "Mute" }).on_press(Message::OscMute(osc_i));
+        let solo =
+            button(if osc.solo { "Soloed" } else { "Solo" }).on_press(Message::OscSolo(osc_i));
+
         column![
             text!("Osc {}", osc_i + 1)
                 .size(24)
@@ -383,6 +1041,7 @@ impl SynthUI {
                 .align_y(Center)
                 .height(Length::FillPortion(10))
                 .width(Length::Fill),
+            row![mute, solo].spacing(8),
             column![text!("Vol.").center(), volume]
                 .align_x(Center)
                 .height(Length::FillPortion(30))
@@ -395,25 +1054,176 @@ impl SynthUI {
                 .align_x(Center)
                 .height(Length::FillPortion(30))
                 .width(Length::Fill),
+            self.table_position(osc_i)
+                .align_x(Center)
+                .height(Length::Shrink)
+                .width(Length::Fill),
+            self.tuning(osc_i)
+                .align_x(Center)
+                .height(Length::Shrink)
+                .width(Length::Fill),
+            self.unison(osc_i)
+                .align_x(Center)
+                .height(Length::Shrink)
+                .width(Length::Fill),
+            self.stereo(osc_i)
+                .align_x(Center)
+                .height(Length::Shrink)
+                .width(Length::Fill),
         ]
         .align_x(Center)
         .height(Length::Fill)
         .width(Length::FillPortion(1))
-        .into()
+    }
+
+    /// where the oscillator reads from multi-frame wave tables and how much the LFO and envelope
+    /// sweep it
+    fn table_position(&self, osc_i: usize) -> Column<'_, Message> {
+        let position = self.state.oscillators[osc_i].position;
+
+        column![
+            text!("Pos."),
+            slider(0.0..=100.0, position.position * 100.0, move |position| {
+                Message::TablePosition {
+                    osc_num: osc_i,
+                    position,
+                }
+            }),
+            text!("LFO"),
+            slider(-100.0..=100.0, position.lfo * 100.0, move |amount| {
+                Message::TablePositionLfo {
+                    osc_num: osc_i,
+                    amount,
+                }
+            }),
+            text!("Env"),
+            slider(-100.0..=100.0, position.env * 100.0, move |amount| {
+                Message::TablePositionEnv {
+                    osc_num: osc_i,
+                    amount,
+                }
+            }),
+        ]
+    }
+
+    /// the oscillator bank's octave and fine tune
+    fn tuning(&self, osc_i: usize) -> Column<'_, Message> {
+        let osc = &self.state.oscillators[osc_i];
+
+        column![
+            row![
+                text!("Oct."),
+                button("-").on_press(Message::OctaveDown(osc_i)),
+                text!("{}", osc.octave),
+                button("+").on_press(Message::OctaveUp(osc_i)),
+            ]
+            .spacing(8)
+            .align_y(Center),
+            text!("Fine {:+.0}c", osc.fine_tune),
+            slider(-100.0..=100.0, osc.fine_tune, move |cents| {
+                Message::FineTune {
+                    osc_num: osc_i,
+                    cents,
+                }
+            }),
+        ]
+    }
+
+    /// how many detuned copies of each note the oscillator bank plays, and how they're spread out
+    fn unison(&self, osc_i: usize) -> Column<'_, Message> {
+        let unison = self.state.oscillators[osc_i].unison;
+
+        column![
+            row![
+                text!("Unison"),
+                button("-").on_press(Message::UnisonDown(osc_i)),
+                text!("{}", unison.voices),
+                button("+").on_press(Message::UnisonUp(osc_i)),
+            ]
+            .spacing(8)
+            .align_y(Center),
+            text!("Detune {:.0}c", unison.detune),
+            slider(0.0..=100.0, unison.detune, move |cents| {
+                Message::UnisonDetune {
+                    osc_num: osc_i,
+                    cents,
+                }
+            }),
+            text!("Width"),
+            slider(0.0..=100.0, unison.width * 100.0, move |width| {
+                Message::UnisonWidth {
+                    osc_num: osc_i,
+                    width,
+                }
+            }),
+        ]
+    }
+
+    /// where the oscillator bank sits in the stereo field and how far its voices are fanned out
+    fn stereo(&self, osc_i: usize) -> Column<'_, Message> {
+        let osc = &self.state.oscillators[osc_i];
+
+        column![
+            text!("Pan"),
+            slider(-100.0..=100.0, osc.pan * 100.0, move |pan| {
+                Message::OscPan {
+                    osc_num: osc_i,
+                    pan,
+                }
+            }),
+            text!("Spread"),
+            slider(0.0..=100.0, osc.spread * 100.0, move |spread| {
+                Message::OscSpread {
+                    osc_num: osc_i,
+                    spread,
+                }
+            }),
+        ]
     }
 
     fn overtones(&self) -> Column<'_, Message> {
         let overtones: Vec<Element<Message>> = (0..10)
             .map(|i| {
-                let slider = vertical_slider(
-                    0.0..=100.0,
-                    self.synth.lock().unwrap().overtones[i].volume * 100.0,
-                    move |vol| Message::OvertoneVolume { overtone: i, vol },
-                );
-                column![text!("{}", i + 1).center(), slider]
-                    // .padding([24, 0])
-                    .width(Length::FillPortion(10))
-                    .into()
+                let overtone = self.state.overtones[i];
+
+                let slider = vertical_slider(0.0..=100.0, overtone.volume * 100.0, move |vol| {
+                    Message::OvertoneVolume { overtone: i, vol }
+                });
+
+                let ratio = match self.ratio_input.as_ref() {
+                    Some((editing, ratio)) if *editing == i => ratio.clone(),
+                    _ => format!("{:.3}", overtone.overtone),
+                };
+
+                let mode = match overtone.mode {
+                    RatioMode::Free => "Free",
+                    RatioMode::Semitone => "Semi",
+                    RatioMode::Harmonic => "Harm",
+                };
+
+                column![
+                    text!("{}", i + 1).center(),
+                    slider,
+                    text_input("ratio", &ratio)
+                        .on_input(move |ratio| Message::OvertoneRatioInput { overtone: i, ratio })
+                        .on_submit(Message::OvertoneRatioSubmit),
+                    row![
+                        button("-").on_press(Message::OvertoneStep {
+                            overtone: i,
+                            up: false
+                        }),
+                        button("+").on_press(Message::OvertoneStep {
+                            overtone: i,
+                            up: true
+                        }),
+                    ],
+                    button(mode).on_press(Message::OvertoneMode(i)),
+                ]
+                .align_x(Center)
+                .spacing(4)
+                // .padding([24, 0])
+                .width(Length::FillPortion(10))
+                .into()
             })
             .collect();
 
@@ -433,41 +1243,67 @@ impl SynthUI {
         .align_x(Center)
         .height(Length::Fill)
         .width(Length::Fill)
-        .into()
     }
 
-    fn vu_meter(&self) -> Element<Message> {
-        vertical_slider(
-            0.0..=100.0,
-            self.synth.lock().unwrap().volume * 100.0,
-            Message::SetVolume,
-        )
-        .into()
+    fn record(&self) -> Column<'_, Message> {
+        let (label, elapsed) = match self.recorder.elapsed() {
+            Some(elapsed) => {
+                let secs = elapsed.as_secs();
+                ("Stop", format!("{:02}:{:02}", secs / 60, secs % 60))
+            }
+            None => ("Record", "--:--".into()),
+        };
+
+        column![
+            button(label).on_press(Message::RecordToggle),
+            text!("{elapsed}")
+        ]
+        .align_x(Center)
+        .width(Length::Fill)
+    }
+
+    fn vu_meter(&self) -> Element<'_, Message> {
+        vertical_slider(0.0..=100.0, self.state.volume * 100.0, Message::SetVolume).into()
     }
 
     fn subscription(&self) -> Subscription<Message> {
-        events().map(|(id, _event)| {
+        let window_events = events().map(|(id, event)| {
             // println!("{id} is now Fullscreen");
             // change_mode(id, window::Mode::Fullscreen);
             // let fun = |_|
-            Message::WindowEvent(id)
+            if event == window::Event::CloseRequested {
+                Message::CloseRequested(id)
+            } else {
+                Message::WindowEvent(id)
+            }
 
             // fun
-        })
+        });
+
+        Subscription::batch([window_events, frames().map(|_| Message::Refresh)])
     }
 }
 
 impl Default for SynthUI {
     fn default() -> Self {
         let synth = {
-            let synth = Synth::new();
+            let mut synth = Synth::new(Player::device_sample_rate());
+            synth.set_wav_tables(WavTable::load_default());
+
+            match Patch::load_default() {
+                Ok(Some(patch)) => synth.apply_patch(&patch),
+                Ok(None) => {}
+                Err(e) => println!("[ERROR] => couldn't load the saved patch: {e}"),
+            }
+
             // println!("synth volume => {}", synth.volume);
-            Arc::new(Mutex::new(synth))
+            synth
         };
 
-        let output = Player {
-            synth: synth.clone(),
-        };
+        let (recorder, tap) = Recorder::new("recordings", synth.sample_rate);
+
+        let (mut output, synth) = Player::new(synth);
+        output.tap = Some(tap);
         let (_stream, stream_handle) = OutputStream::try_default().unwrap();
 
         // start output
@@ -478,14 +1314,25 @@ impl Default for SynthUI {
             }
         });
 
+        let bank = Arc::new(Mutex::new(Bank::load_default()));
+        let router = MidiRouter::load_default(Some(bank.clone()));
+
         let s = synth.clone();
-        let jh_2 = spawn(move || con_to_serial(s));
+        let r = router.clone();
+        let jh_2 = spawn(move || con_to_serial(s, r));
         let jhs = (jh_1, jh_2);
 
         Self {
+            state: synth.state(),
             synth,
             jhs,
             _stream,
+            recorder,
+            bank,
+            preset_name: String::new(),
+            router,
+            learning: false,
+            ratio_input: None,
         }
     }
 }
@@ -494,31 +1341,20 @@ fn main() -> iced::Result {
     iced::application(SynthUI::title, SynthUI::update, SynthUI::view)
         .theme(|_| Theme::CatppuccinMocha)
         .subscription(SynthUI::subscription)
+        .exit_on_close_request(false)
         .centered()
         .run()
     // .and_then(|_| )
 }
 
-fn con_to_serial(s: Arc<Mutex<Synth>>) {
-    if let Err(e) = run_midi(s) {
+fn con_to_serial(s: SynthHandle, r: MidiRouter) {
+    if let Err(e) = run_midi(s, r) {
         println!("[ERROR] => Serial MIDI input error: {e}");
         // exit(1);
     }
 }
 
-pub fn decode_hex(s: &str) -> Result<Vec<u8>> {
-    let s = s.replace("\r\n", "");
-    // println!("{s:#?}");
-
-    let bytes: Result<Vec<u8>, ParseIntError> = (0..s.len())
-        .step_by(2)
-        .map(|i| u8::from_str_radix(&s[i..i + 2], 16))
-        .collect();
-
-    Ok(bytes?)
-}
-
-fn run_midi(synth: Arc<Mutex<Synth>>) -> Result<()> {
+fn run_midi(synth: SynthHandle, router: MidiRouter) -> Result<()> {
     let Some(Ok(port)) = glob::glob("/dev/ttyACM*")?.next() else {
         // continue;
         bail!("no serial ports found");
@@ -540,56 +1376,8 @@ fn run_midi(synth: Arc<Mutex<Synth>>) -> Result<()> {
         }
 
         // parse into midi command
-        let synth = synth.clone();
-
         if let Ok(midi_cmd) = decode_hex(&midi_cmd) {
-            spawn(move || {
-                let message = MidiMessage::from(midi_cmd.as_ref());
-                // println!("midi message => {message:?}");
-                // do midi stuff
-
-                match message {
-                    MidiMessage::Invalid => {
-                        // println!("midi_cmd_buf => {midi_cmd:?}");
-                        println!("midi cmd => {:?}", MidiMessage::from(midi_cmd.as_ref()));
-                        println!("midi_cmd -> {midi_cmd:?}");
-                        println!("midi command invalid");
-                    }
-                    MidiMessage::NoteOn(_, KeyEvent { key, value }) => {
-                        synth.lock().unwrap().play(key, value)
-                    }
-                    MidiMessage::NoteOff(_, KeyEvent { key, value: _ }) => {
-                        synth.lock().unwrap().stop(key)
-                    }
-                    MidiMessage::PitchBend(_, lsb, msb) => {
-                        let bend = i16::from_le_bytes([lsb, msb]) as f32 / (32_000.0 * 0.5) - 1.0;
-
-                        if bend > 0.026 || bend < -0.026 {
-                            synth.lock().unwrap().bend_all(bend);
-                        } else {
-                            synth.lock().unwrap().unbend();
-                        }
-                    }
-                    MidiMessage::ControlChange(_, ControlEvent { control, value }) => {
-                        let value = value as f32 / 127.0;
-
-                        match control {
-                            // 70 => synth.lock().unwrap().set_volume(value),
-                            70 => synth.lock().unwrap().set_atk(value),
-                            71 => synth.lock().unwrap().set_decay(value),
-                            72 => synth.lock().unwrap().set_sus(value),
-                            73 => synth.lock().unwrap().set_release(value),
-                            74 => synth.lock().unwrap().set_cutoff(value),
-                            75 => synth.lock().unwrap().set_resonace(value),
-                            76 => synth.lock().unwrap().set_chorus_depth(value),
-                            77 => synth.lock().unwrap().set_chorus_speed(value),
-                            1 => synth.lock().unwrap().set_leslie_speed(value),
-                            _ => {}
-                        }
-                    }
-                    _ => {} // }
-                }
-            });
+            router.handle_bytes(&synth, &midi_cmd);
         } else {
             println!("bad HEX");
         }
@@ -1,25 +1,33 @@
 use anyhow::{bail, Result};
-use midi_control::{ControlEvent, KeyEvent, MidiMessage};
 use rodio::OutputStream;
-use serialport;
 use std::{
-    i16,
     io::{BufRead, BufReader},
-    num::ParseIntError,
     process::exit,
     sync::{Arc, Mutex},
     thread::spawn,
     time::Duration,
 };
-use synth_rt::{synth::Synth, Player};
+use synth_rt::{
+    bank::Bank,
+    event::SynthHandle,
+    midi::{decode_hex, MidiRouter},
+    patch::Patch,
+    synth::Synth,
+    wav_table::WavTable,
+    Player,
+};
 
 fn main() -> Result<()> {
-    // build synth in arc mutex
-    let synth = Arc::new(Mutex::new(Synth::new()));
+    let mut synth = Synth::new(Player::device_sample_rate());
+    synth.set_wav_tables(WavTable::load_default());
 
-    let output = Player {
-        synth: synth.clone(),
-    };
+    match Patch::load_default() {
+        Ok(Some(patch)) => synth.apply_patch(&patch),
+        Ok(None) => {}
+        Err(e) => println!("[ERROR] => couldn't load the saved patch: {e}"),
+    }
+
+    let (output, synth) = Player::new(synth);
     let (_stream, stream_handle) = OutputStream::try_default().unwrap();
 
     // start output
@@ -30,22 +38,12 @@ fn main() -> Result<()> {
         }
     });
 
-    run_midi(synth)
-}
-
-pub fn decode_hex(s: &str) -> Result<Vec<u8>> {
-    let s = s.replace("\r\n", "");
-    // println!("{s:#?}");
+    let bank = Arc::new(Mutex::new(Bank::load_default()));
 
-    let bytes: Result<Vec<u8>, ParseIntError> = (0..s.len())
-        .step_by(2)
-        .map(|i| u8::from_str_radix(&s[i..i + 2], 16))
-        .collect();
-
-    Ok(bytes?)
+    run_midi(synth, MidiRouter::load_default(Some(bank)))
 }
 
-fn run_midi(synth: Arc<Mutex<Synth>>) -> Result<()> {
+fn run_midi(synth: SynthHandle, router: MidiRouter) -> Result<()> {
     let Some(Ok(port)) = glob::glob("/dev/ttyACM*")?.next() else {
         // continue;
         bail!("no serial ports found");
@@ -67,54 +65,8 @@ fn run_midi(synth: Arc<Mutex<Synth>>) -> Result<()> {
         }
 
         // parse into midi command
-        let synth = synth.clone();
-
         if let Ok(midi_cmd) = decode_hex(&midi_cmd) {
-            spawn(move || {
-                let message = MidiMessage::from(midi_cmd.as_ref());
-                // do midi stuff
-
-                match message {
-                    MidiMessage::Invalid => {
-                        println!("midi_cmd_buf => {midi_cmd:?}");
-                        println!("midi cmd => {:?}", MidiMessage::from(midi_cmd.as_ref()));
-                        println!("midi_cmd -> {midi_cmd:?}");
-                        println!("midi command invalid");
-                    }
-                    MidiMessage::NoteOn(_, KeyEvent { key, value }) => {
-                        synth.lock().unwrap().play(key, value)
-                    }
-                    MidiMessage::NoteOff(_, KeyEvent { key, value: _ }) => {
-                        synth.lock().unwrap().stop(key)
-                    }
-                    MidiMessage::PitchBend(_, lsb, msb) => {
-                        let bend = i16::from_le_bytes([lsb, msb]) as f32 / (32_000.0 * 0.5) - 1.0;
-
-                        if bend > 0.026 || bend < -0.026 {
-                            synth.lock().unwrap().bend_all(bend);
-                        } else {
-                            synth.lock().unwrap().unbend();
-                        }
-                    }
-                    MidiMessage::ControlChange(_, ControlEvent { control, value }) => {
-                        let value = value as f32 / 127.0;
-
-                        match control {
-                            70 => synth.lock().unwrap().set_volume(value),
-                            71 => synth.lock().unwrap().set_atk(value),
-                            72 => synth.lock().unwrap().set_decay(value),
-                            73 => synth.lock().unwrap().set_sus(value),
-                            74 => synth.lock().unwrap().set_cutoff(value),
-                            75 => synth.lock().unwrap().set_resonace(value),
-                            76 => synth.lock().unwrap().set_chorus_depth(value),
-                            77 => synth.lock().unwrap().set_chorus_speed(value),
-                            1 => synth.lock().unwrap().set_leslie_speed(value),
-                            _ => {}
-                        }
-                    }
-                    _ => {} // }
-                }
-            });
+            router.handle_bytes(&synth, &midi_cmd);
         } else {
             println!("bad HEX");
         }
@@ -0,0 +1,163 @@
+use crate::{midi::DEFAULT_CC_MAP, synth::Param};
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf};
+
+/// how a CC value is shaped before it's scaled to a binding's min and max
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum Curve {
+    #[default]
+    Linear,
+    /// more resolution at the bottom of the range
+    Exponential,
+    /// more resolution at the top of the range
+    Logarithmic,
+}
+
+impl Curve {
+    /// shapes a value in 0.0..=1.0
+    pub fn apply(&self, value: f32) -> f32 {
+        match self {
+            Self::Linear => value,
+            Self::Exponential => value * value,
+            Self::Logarithmic => value.sqrt(),
+        }
+    }
+}
+
+/// binds a CC number to a synth parameter
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct CcBinding {
+    pub cc: u8,
+    /// the MIDI channel (1 - 16) this binding listens to. listens to every channel if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel: Option<u8>,
+    pub param: Param,
+    /// the parameter value sent when the CC is at 0
+    #[serde(default)]
+    pub min: f32,
+    /// the parameter value sent when the CC is at 127
+    #[serde(default = "CcBinding::default_max")]
+    pub max: f32,
+    #[serde(default)]
+    pub curve: Curve,
+}
+
+impl CcBinding {
+    pub fn new(cc: u8, channel: Option<u8>, param: Param) -> Self {
+        Self {
+            cc,
+            channel,
+            param,
+            min: 0.0,
+            max: Self::default_max(),
+            curve: Curve::Linear,
+        }
+    }
+
+    fn default_max() -> f32 {
+        1.0
+    }
+
+    /// true if this binding listens to `cc` on `channel`
+    pub fn matches(&self, cc: u8, channel: u8) -> bool {
+        self.cc == cc && self.channel.is_none_or(|ch| ch == channel)
+    }
+
+    /// converts a CC value to the value of the parameter
+    pub fn scale(&self, value: u8) -> f32 {
+        self.min + (self.max - self.min) * self.curve.apply(value as f32 / 127.0)
+    }
+}
+
+/// the user editable mapping from CC numbers to synth parameters
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct CcMap {
+    pub bindings: Vec<CcBinding>,
+    /// where the map is saved to when a binding is learned
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+}
+
+impl Default for CcMap {
+    fn default() -> Self {
+        Self {
+            bindings: DEFAULT_CC_MAP
+                .iter()
+                .map(|(cc, param)| CcBinding::new(*cc, None, *param))
+                .collect(),
+            path: None,
+        }
+    }
+}
+
+impl CcMap {
+    /// reads a CC map from a TOML file
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let mut map: Self = toml::from_str(&fs::read_to_string(&path)?)?;
+        map.path = Some(path);
+
+        Ok(map)
+    }
+
+    /// writes the CC map to the file it was loaded from
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = self.path.as_ref() else {
+            bail!("the CC map has no file to save to");
+        };
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        fs::write(path, toml::to_string_pretty(self)?)?;
+
+        Ok(())
+    }
+
+    /// where the CC map is kept. (`~/.config/synth-rt/cc_map.toml` on linux)
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("synth-rt").join("cc_map.toml"))
+    }
+
+    /// loads the CC map from `default_path`. if there isn't one yet, the default map is written
+    /// there so it can be edited.
+    pub fn load_default() -> Result<Self> {
+        let Some(path) = Self::default_path() else {
+            return Ok(Self::default());
+        };
+
+        if path.exists() {
+            return Self::load(path);
+        }
+
+        let map = Self {
+            path: Some(path),
+            ..Default::default()
+        };
+        map.save()?;
+
+        Ok(map)
+    }
+
+    /// the binding for `cc` on `channel`, if there is one
+    pub fn find(&self, cc: u8, channel: u8) -> Option<&CcBinding> {
+        self.bindings
+            .iter()
+            .find(|binding| binding.matches(cc, channel))
+    }
+
+    /// binds `cc` on `channel` to `param`, replacing any binding that used that CC or parameter.
+    /// bindings that listen on every channel overlap all the others, so a binding on that CC is
+    /// replaced if either one has no channel.
+    pub fn bind(&mut self, cc: u8, channel: Option<u8>, param: Param) {
+        self.bindings.retain(|binding| {
+            let overlaps =
+                binding.channel.is_none() || channel.is_none() || binding.channel == channel;
+
+            binding.param != param && !(binding.cc == cc && overlaps)
+        });
+        self.bindings.push(CcBinding::new(cc, channel, param));
+    }
+}
@@ -1,26 +1,41 @@
-use crate::SAMPLE_RATE;
+use crate::DEFAULT_SAMPLE_RATE;
+
+/// how far behind the left channel's tap the right one reads at full width, in seconds
+const MAX_WIDTH_DELAY: f32 = 0.012;
 
 pub struct Chorus {
+    pub sample_rate: u32,
     pub size: usize,
-    pub buff: [f32; SAMPLE_RATE as usize],
+    /// one second of delay
+    pub buff: Vec<f32>,
     pub i: usize,
     pub step: usize,
     pub volume: f32,
     pub speed: f32,
+    /// how far apart the left and right channels are pulled, (0.0 is mono, 1.0 is widest)
+    pub width: f32,
+    /// how many samples the right channel's tap trails the left one
+    pub width_step: usize,
     pub power: bool,
 }
 
 impl Chorus {
-    pub fn new() -> Self {
-        Self {
-            size: SAMPLE_RATE as usize,
-            buff: [0.0; SAMPLE_RATE as usize],
+    pub fn new(sample_rate: u32) -> Self {
+        let mut chorus = Self {
+            sample_rate,
+            size: sample_rate as usize,
+            buff: vec![0.0; sample_rate as usize],
             i: 0,
             step: 1,
             volume: 0.75,
             speed: 0.0,
+            width: 0.0,
+            width_step: 0,
             power: true,
-        }
+        };
+        chorus.set_width(0.5);
+
+        chorus
     }
 
     pub fn get_sample(&mut self, input_sample: f32) -> f32 {
@@ -40,14 +55,45 @@ impl Chorus {
         }
     }
 
+    /// runs a stereo frame through the chorus. the right channel reads the delay line a little
+    /// later than the left, by `width`, so the two sides drift apart.
+    pub fn get_frame(&mut self, left: f32, right: f32) -> (f32, f32) {
+        if !self.power {
+            return (left, right);
+        }
+
+        let trailing = (self.i + self.size - self.width_step) % self.size;
+        let chorus_l = ((self.buff[self.i] * self.volume) + left).tanh();
+        let chorus_r = ((self.buff[trailing] * self.volume) + right).tanh();
+        self.buff[(self.i + self.step) % self.size] = (chorus_l + chorus_r) * 0.5;
+        self.i = (self.i + 1) % self.size;
+
+        (chorus_l, chorus_r)
+    }
+
+    /// runs every frame of `left` and `right` through the chorus, into `out_l` and `out_r`
+    pub fn process(&mut self, left: &[f32], right: &[f32], out_l: &mut [f32], out_r: &mut [f32]) {
+        let frames = left.iter().zip(right);
+
+        for ((out_l, out_r), (left, right)) in out_l.iter_mut().zip(out_r.iter_mut()).zip(frames) {
+            (*out_l, *out_r) = self.get_frame(*left, *right);
+        }
+    }
+
     /// sets speed, takes speed in seconds
     pub fn set_speed(&mut self, speed: f32) {
         // info!("speed: {}", speed);
         self.speed = speed;
-        self.step = (SAMPLE_RATE as f32 * (speed * 0.05)) as usize;
+        self.step = (self.sample_rate as f32 * (speed * 0.05)) as usize;
         // info!("step:  {}", self.step);
     }
 
+    /// sets the stereo width, (0.0..=1.0)
+    pub fn set_width(&mut self, width: f32) {
+        self.width = width.clamp(0.0, 1.0);
+        self.width_step = (self.sample_rate as f32 * MAX_WIDTH_DELAY * self.width) as usize;
+    }
+
     pub fn set_volume(&mut self, volume: f32) {
         self.volume = volume;
     }
@@ -60,3 +106,9 @@ impl Chorus {
         self.power = !self.power;
     }
 }
+
+impl Default for Chorus {
+    fn default() -> Self {
+        Self::new(DEFAULT_SAMPLE_RATE)
+    }
+}
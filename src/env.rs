@@ -1,4 +1,6 @@
-use crate::SAMPLE_RATE;
+use crate::DEFAULT_SAMPLE_RATE;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
 
 static UNPRESSED: usize = 0;
 static ATTACK: usize = 1;
@@ -6,129 +8,322 @@ static DECAY: usize = 2;
 static SUSTAIN: usize = 3;
 static RELEASE: usize = 4;
 
+/// how much a curve with an `amount` of 1.0 bends
+const MAX_CURVATURE: f32 = 4.0;
+
+/// one of the stages of an envelope that moves from one level to another
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Stage {
+    Attack,
+    Decay,
+    Release,
+}
+
+impl Stage {
+    pub const ALL: [Self; 3] = [Self::Attack, Self::Decay, Self::Release];
+
+    fn phase(&self) -> usize {
+        match self {
+            Self::Attack => ATTACK,
+            Self::Decay => DECAY,
+            Self::Release => RELEASE,
+        }
+    }
+}
+
+/// one of the envelopes each voice has
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Envelope {
+    /// sets the voice's level
+    Amp,
+    /// sweeps the low pass filter's cutoff
+    Filter,
+    /// moves whatever the voice's `ModTarget` is
+    Mod,
+}
+
+impl Envelope {
+    pub const ALL: [Self; 3] = [Self::Amp, Self::Filter, Self::Mod];
+}
+
+impl Display for Envelope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Amp => write!(f, "Amp"),
+            Self::Filter => write!(f, "Filter"),
+            Self::Mod => write!(f, "Mod"),
+        }
+    }
+}
+
+/// what the modulation envelope moves
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum ModTarget {
+    /// up to an octave up or down
+    #[default]
+    Pitch,
+    /// up to four octaves up or down, on top of the filter envelope
+    Cutoff,
+    /// up to double or half the resonance
+    Resonance,
+    /// up to the whole way through every bank's wave tables, on top of their "Env" amounts
+    TablePosition,
+}
+
+impl ModTarget {
+    pub const ALL: [Self; 4] = [
+        Self::Pitch,
+        Self::Cutoff,
+        Self::Resonance,
+        Self::TablePosition,
+    ];
+}
+
+impl Display for ModTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Pitch => write!(f, "Pitch"),
+            Self::Cutoff => write!(f, "Cutoff"),
+            Self::Resonance => write!(f, "Res."),
+            Self::TablePosition => write!(f, "Table"),
+        }
+    }
+}
+
+/// how the level moves through an envelope stage
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum CurveShape {
+    /// a straight line
+    #[default]
+    Linear,
+    /// bends like an exponential, rises slowly then quickly and falls quickly then slowly. (like
+    /// an analog envelope's decay)
+    Exponential,
+    /// bends the other way, rises quickly then slowly and falls slowly then quickly
+    Logarithmic,
+}
+
+impl CurveShape {
+    pub const ALL: [Self; 3] = [Self::Linear, Self::Exponential, Self::Logarithmic];
+}
+
+impl Display for CurveShape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Linear => write!(f, "Lin."),
+            Self::Exponential => write!(f, "Exp."),
+            Self::Logarithmic => write!(f, "Log."),
+        }
+    }
+}
+
+/// the shape of an envelope stage
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Curve {
+    pub shape: CurveShape,
+    /// how strongly the stage bends, (0.0 is a straight line, 1.0 is the most)
+    pub amount: f32,
+}
+
+impl Curve {
+    /// how far from the start level to the end level a stage is `progress` of the way through it,
+    /// (both 0.0..=1.0)
+    pub fn at(&self, progress: f32, rising: bool) -> f32 {
+        let curvature = 1.0 + self.amount.clamp(0.0, 1.0) * MAX_CURVATURE;
+
+        let slow_start = match self.shape {
+            CurveShape::Linear => return progress,
+            CurveShape::Exponential => rising,
+            CurveShape::Logarithmic => !rising,
+        };
+
+        if slow_start {
+            progress.powf(curvature)
+        } else {
+            1.0 - (1.0 - progress).powf(curvature)
+        }
+    }
+}
+
+impl Default for Curve {
+    fn default() -> Self {
+        Self {
+            shape: CurveShape::Linear,
+            amount: 0.5,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct ADSR {
     pub sample_rate: u32,
     pub phase: usize,
     base_params: [f32; 5],
+    /// how much the progress through each stage goes up every sample
     tweek_env_by: [f32; 5],
+    curves: [Curve; 5],
+    /// how far through the current stage the envelope is, (0.0..=1.0)
+    progress: f32,
+    /// the level the current stage started at
+    from: f32,
     env: f32,
 }
 
 impl ADSR {
-    pub fn new() -> Self {
-        let base_params = [0.0, 0.1, 0.1, 0.5, 0.1];
-
-        Self {
-            sample_rate: SAMPLE_RATE,
+    pub fn new(sample_rate: u32) -> Self {
+        let mut env = Self {
+            sample_rate,
             phase: 0,
-            base_params,
-            tweek_env_by: Self::calc_tweek_by(base_params),
+            base_params: [0.0, 0.1, 0.1, 0.5, 0.1],
+            tweek_env_by: [0.0; 5],
+            curves: [Curve::default(); 5],
+            progress: 0.0,
+            from: 0.0,
             env: 0.0,
-        }
+        };
+        env.tweek_env_by = env.calc_tweek_by();
+
+        env
     }
 
-    fn calc_tweek_by(base_params: [f32; 5]) -> [f32; 5] {
+    fn calc_tweek_by(&self) -> [f32; 5] {
+        let base_params = self.base_params;
         let mut tweek_env_by = [0.0; 5];
 
-        tweek_env_by[ATTACK] = Self::calc_atk(base_params[ATTACK]);
-        tweek_env_by[DECAY] = Self::calc_decay(base_params[DECAY], base_params[SUSTAIN]);
-        tweek_env_by[RELEASE] = Self::calc_release(base_params[RELEASE], base_params[SUSTAIN]);
-
-        // println!("{}", tweek_env_by[DECAY]);
+        tweek_env_by[ATTACK] = self.calc_step(base_params[ATTACK]);
+        tweek_env_by[DECAY] = self.calc_step(base_params[DECAY]);
+        tweek_env_by[RELEASE] = self.calc_step(base_params[RELEASE]);
 
         tweek_env_by
     }
 
-    fn calc_atk(atk_speed: f32) -> f32 {
-        1.0 / (atk_speed * SAMPLE_RATE as f32)
-    }
-
-    fn calc_decay(decay_speed: f32, sustain_level: f32) -> f32 {
-        (-1.0 + sustain_level) / (decay_speed * SAMPLE_RATE as f32)
-    }
-
-    fn calc_release(release_speed: f32, sustain_level: f32) -> f32 {
-        (-1.0 + sustain_level) / (release_speed * SAMPLE_RATE as f32)
+    /// how much the progress through a stage that takes `seconds` goes up every sample
+    fn calc_step(&self, seconds: f32) -> f32 {
+        1.0 / (seconds * self.sample_rate as f32).max(1.0)
     }
 
     pub fn set_atk(&mut self, atk: f32) {
         // set attack
         self.base_params[ATTACK] = atk;
 
-        self.tweek_env_by[ATTACK] = Self::calc_atk(atk);
+        self.tweek_env_by[ATTACK] = self.calc_step(atk);
     }
 
     pub fn set_decay(&mut self, decay: f32) {
         // set decay
         self.base_params[DECAY] = decay;
 
-        self.tweek_env_by[DECAY] = Self::calc_decay(decay, self.base_params[SUSTAIN]);
+        self.tweek_env_by[DECAY] = self.calc_step(decay);
     }
 
     pub fn set_sus(&mut self, sustain: f32) {
         // set sustain
         self.base_params[SUSTAIN] = sustain;
-
-        self.tweek_env_by[DECAY] =
-            Self::calc_decay(self.base_params[DECAY], self.base_params[SUSTAIN]);
-        self.tweek_env_by[RELEASE] =
-            Self::calc_release(self.base_params[RELEASE], self.base_params[SUSTAIN]);
     }
 
     pub fn set_release(&mut self, release: f32) {
         let release = release * 0.25;
 
         self.base_params[RELEASE] = release;
-        self.tweek_env_by[RELEASE] = Self::calc_release(release, self.base_params[SUSTAIN]);
+        self.tweek_env_by[RELEASE] = self.calc_step(release);
+    }
+
+    pub fn set_curve(&mut self, stage: Stage, curve: Curve) {
+        self.curves[stage.phase()] = curve;
+    }
+
+    /// attack time in seconds
+    pub fn atk(&self) -> f32 {
+        self.base_params[ATTACK]
+    }
+
+    /// decay time in seconds
+    pub fn decay(&self) -> f32 {
+        self.base_params[DECAY]
+    }
+
+    /// sustain level
+    pub fn sus(&self) -> f32 {
+        self.base_params[SUSTAIN]
+    }
+
+    /// release in the same units `set_release` takes
+    pub fn release_time(&self) -> f32 {
+        self.base_params[RELEASE] / 0.25
+    }
+
+    pub fn curve(&self, stage: Stage) -> Curve {
+        self.curves[stage.phase()]
+    }
+
+    /// the current level of the envelope
+    pub fn level(&self) -> f32 {
+        self.env
+    }
+
+    /// the level the current stage moves towards
+    fn target(&self) -> f32 {
+        match self.phase {
+            p if p == ATTACK => 1.0,
+            p if p == DECAY => self.base_params[SUSTAIN],
+            _ => 0.0,
+        }
+    }
+
+    /// starts a stage from the current level
+    fn start(&mut self, phase: usize) {
+        self.phase = phase;
+        self.progress = 0.0;
+        self.from = self.env;
     }
 
     /// used to generate an env sample
     pub fn get_samnple(&mut self) -> f32 {
-        self.env += self.tweek_env_by[self.phase];
-        // println!("tweak => {}", self.tweek_env_by[self.phase]);
-        // println!("phase => {}", self.phase);
-
-        if self.env > 1.0 && self.phase == ATTACK {
-            // println!("now decay");
-            self.phase = DECAY;
-            self.env = 1.0;
-            // println!("tweak => {}", self.tweek_env_by[self.phase]);
-            // println!("phase => {}", self.phase);
-            // println!("env => {}", self.env);
-        } else if self.env < self.base_params[SUSTAIN] && self.phase == DECAY {
-            // println!("now sustain");
-            self.phase = SUSTAIN;
+        if self.phase == UNPRESSED {
+            return self.env;
+        }
+
+        if self.phase == SUSTAIN {
             self.env = self.base_params[SUSTAIN];
-            // println!("tweak => {}", self.tweek_env_by[self.phase]);
-            // println!("phase => {}", self.phase);
-            // println!("env => {}", self.env);
-        } else if self.env <= 0.0 {
-            // println!("now released");
-            self.phase = RELEASE;
-            self.env = 0.0;
+            return self.env;
         }
 
-        // if self.phase == RELEASE {
-        //     println!("RELEASE");
-        //     println!("tweak => {}", self.tweek_env_by[self.phase]);
-        //     println!("phase => {}", self.phase);
-        //     println!("env => {}", self.env);
-        // }
+        self.progress = (self.progress + self.tweek_env_by[self.phase]).min(1.0);
+
+        let to = self.target();
+        let curve = self.curves[self.phase].at(self.progress, to > self.from);
+        self.env = self.from + (to - self.from) * curve;
+
+        if self.progress >= 1.0 {
+            self.env = to;
+
+            if self.phase == ATTACK {
+                self.start(DECAY);
+            } else if self.phase == DECAY {
+                self.phase = SUSTAIN;
+            }
+        }
 
         self.env
     }
 
-    /// presses the key
+    /// presses the key. the attack starts from the current level so retriggering a note that's
+    /// still sounding doesn't click.
     pub fn press(&mut self) {
-        self.phase = ATTACK;
-        self.env = 0.0;
+        self.start(ATTACK);
     }
 
-    /// Release the key if pressed
+    /// Release the key if pressed. the release starts from the current level, so letting go
+    /// during the attack or decay doesn't click.
     pub fn release(&mut self) {
-        self.phase = RELEASE;
-        self.env = self.base_params[SUSTAIN];
+        self.start(RELEASE);
+    }
+
+    /// silences the envelope
+    pub fn reset(&mut self) {
+        self.phase = UNPRESSED;
+        self.progress = 0.0;
+        self.env = 0.0;
     }
 
     /// returns true if the env filter is not released
@@ -136,3 +331,9 @@ impl ADSR {
         self.phase != RELEASE && self.phase != UNPRESSED
     }
 }
+
+impl Default for ADSR {
+    fn default() -> Self {
+        Self::new(DEFAULT_SAMPLE_RATE)
+    }
+}
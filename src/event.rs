@@ -0,0 +1,173 @@
+use crate::{
+    patch::Patch,
+    synth::{Param, Synth, WaveTables},
+};
+use arc_swap::ArcSwap;
+use rtrb::{Consumer, Producer, RingBuffer};
+use std::sync::{Arc, Mutex};
+
+/// how many events can be waiting for the audio thread at once
+pub const EVENT_QUEUE_SIZE: usize = 1024;
+
+/// how many snapshots of the synth's settings can be waiting for the UI at once
+const PUBLISH_QUEUE_SIZE: usize = 8;
+
+/// a change sent to the audio thread. everything that plays or edits the synth while it's running
+/// goes through one of these.
+pub enum Event {
+    NoteOn {
+        note: u8,
+        velocity: u8,
+    },
+    NoteOff(u8),
+    /// about -1.0..=1.0, see `midi::pitch_bend`
+    PitchBend(f32),
+    StopAll,
+    SustainPedal(bool),
+    SostenutoPedal(bool),
+    SoftPedal(bool),
+    Param(Param, f32),
+    Patch(Box<Patch>),
+    /// any other edit, (used by the UI for settings that aren't a `Param`)
+    Edit(Box<dyn FnMut(&mut Synth) + Send>),
+}
+
+impl Event {
+    pub fn apply(&mut self, synth: &mut Synth) {
+        match *self {
+            Self::NoteOn { note, velocity } => synth.play(note, velocity),
+            Self::NoteOff(note) => synth.stop(note),
+            // small bends are treated as none so a pitch wheel that doesn't center exactly stays
+            // in tune
+            Self::PitchBend(bend) if !(-0.026..=0.026).contains(&bend) => synth.bend_all(bend),
+            Self::PitchBend(_) => synth.unbend(),
+            Self::StopAll => synth.stop_all(),
+            Self::SustainPedal(down) => synth.set_sustain_pedal(down),
+            Self::SostenutoPedal(down) => synth.set_sostenuto_pedal(down),
+            Self::SoftPedal(down) => synth.set_soft_pedal(down),
+            Self::Param(param, value) => synth.set_param(param, value),
+            Self::Patch(ref patch) => synth.apply_patch(patch),
+            Self::Edit(ref mut edit) => edit(synth),
+        }
+    }
+
+    /// true if the event can change what `Synth::snapshot_patch` returns
+    pub fn edits(&self) -> bool {
+        matches!(self, Self::Param(..) | Self::Patch(_) | Self::Edit(_))
+    }
+
+    /// true if the event owns memory that has to be freed when it's dropped
+    pub fn is_boxed(&self) -> bool {
+        matches!(self, Self::Patch(_) | Self::Edit(_))
+    }
+}
+
+/// the UI and MIDI threads' end of a running synth. events are queued to the audio thread, which
+/// publishes the synth's settings back after applying them. cloning it is cheap, every clone
+/// feeds the same queue.
+#[derive(Clone)]
+pub struct SynthHandle {
+    /// the queue has a single producer, the threads sending events take turns with it. the audio
+    /// thread's end is never locked.
+    events: Arc<Mutex<Producer<Event>>>,
+    /// boxed events the audio thread is done with, sent back to be freed here
+    returned: Arc<Mutex<Consumer<Event>>>,
+    /// the synth's settings, as the audio thread publishes them
+    published: Arc<Mutex<Consumer<Patch>>>,
+    state: Arc<ArcSwap<Patch>>,
+    /// the wave tables being played
+    pub wave_tables: Arc<ArcSwap<WaveTables>>,
+}
+
+impl SynthHandle {
+    /// queues `event` for the audio thread. if the queue is full the event is dropped.
+    pub fn send(&self, event: Event) {
+        self.free_returned();
+
+        if self.events.lock().unwrap().push(event).is_err() {
+            println!("[ERROR] => the event queue is full, dropped an event");
+        }
+    }
+
+    /// queues an edit to be run on the audio thread
+    pub fn edit(&self, edit: impl FnMut(&mut Synth) + Send + 'static) {
+        self.send(Event::Edit(Box::new(edit)))
+    }
+
+    /// the synth's settings as of the last block the audio thread published
+    pub fn state(&self) -> Arc<Patch> {
+        self.free_returned();
+
+        let newest = {
+            let mut published = self.published.lock().unwrap();
+            std::iter::from_fn(|| published.pop().ok()).last()
+        };
+
+        if let Some(patch) = newest {
+            self.state.store(Arc::new(patch));
+        }
+
+        self.state.load_full()
+    }
+
+    /// drops the events the audio thread sent back
+    fn free_returned(&self) {
+        let mut returned = self.returned.lock().unwrap();
+        while returned.pop().is_ok() {}
+    }
+}
+
+/// the audio thread's end of a `SynthHandle`
+pub struct EventReceiver {
+    events: Consumer<Event>,
+    returned: Producer<Event>,
+    published: Producer<Patch>,
+    /// true if the synth was edited since its settings were last published
+    edited: bool,
+}
+
+impl EventReceiver {
+    /// applies every queued event to `synth`, then publishes its settings if any of them were
+    /// edits. never blocks, allocates or frees.
+    pub fn apply(&mut self, synth: &mut Synth) {
+        while let Ok(mut event) = self.events.pop() {
+            self.edited |= event.edits();
+            event.apply(synth);
+
+            // if the queue back is full the event has to be freed here
+            if event.is_boxed() {
+                let _ = self.returned.push(event);
+            }
+        }
+
+        // if the UI hasn't caught up the settings are published on a later block
+        if self.edited && !self.published.is_full() {
+            let _ = self.published.push(synth.snapshot_patch());
+            self.edited = false;
+        }
+    }
+}
+
+/// makes the event queue for `synth`
+pub fn queue(synth: &Synth) -> (SynthHandle, EventReceiver) {
+    let (producer, consumer) = RingBuffer::new(EVENT_QUEUE_SIZE);
+    // every boxed event is sent through the event queue, so it's never more than that behind
+    let (returned, to_free) = RingBuffer::new(EVENT_QUEUE_SIZE);
+    let (publisher, published) = RingBuffer::new(PUBLISH_QUEUE_SIZE);
+
+    (
+        SynthHandle {
+            events: Arc::new(Mutex::new(producer)),
+            returned: Arc::new(Mutex::new(to_free)),
+            published: Arc::new(Mutex::new(published)),
+            state: Arc::new(ArcSwap::from_pointee(synth.snapshot_patch())),
+            wave_tables: synth.wave_tables.clone(),
+        },
+        EventReceiver {
+            events: consumer,
+            returned,
+            published: publisher,
+            edited: false,
+        },
+    )
+}
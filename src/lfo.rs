@@ -1,4 +1,4 @@
-use crate::SAMPLE_RATE;
+use crate::DEFAULT_SAMPLE_RATE;
 
 pub const LFO_WAVE_TABLE_SIZE: usize = 128;
 
@@ -8,16 +8,18 @@ pub struct LFO {
     wave_table: [f32; LFO_WAVE_TABLE_SIZE],
     index: f32,
     index_increment: f32,
+    frequency: f32,
     volume: f32,
 }
 
 impl LFO {
-    pub fn new() -> Self {
+    pub fn new(sample_rate: u32) -> Self {
         Self {
-            sample_rate: SAMPLE_RATE,
+            sample_rate,
             wave_table: Self::build_wave_table(),
             index: 0.0,
             index_increment: 0.0,
+            frequency: 0.0,
             volume: 1.0,
         }
     }
@@ -25,14 +27,15 @@ impl LFO {
     fn build_wave_table() -> [f32; LFO_WAVE_TABLE_SIZE] {
         let mut wave_table = [0.0; LFO_WAVE_TABLE_SIZE];
 
-        for i in 0..LFO_WAVE_TABLE_SIZE {
-            wave_table[i] =
+        for (i, sample) in wave_table.iter_mut().enumerate() {
+            *sample =
                 (2.0 * core::f64::consts::PI * i as f64 / LFO_WAVE_TABLE_SIZE as f64).sin() as f32
         }
 
         wave_table
     }
     pub fn set_frequency(&mut self, frequency: f32) {
+        self.frequency = frequency;
         self.index_increment = frequency * LFO_WAVE_TABLE_SIZE as f32 / self.sample_rate as f32;
     }
 
@@ -40,6 +43,14 @@ impl LFO {
         self.volume = volume;
     }
 
+    pub fn frequency(&self) -> f32 {
+        self.frequency
+    }
+
+    pub fn volume(&self) -> f32 {
+        self.volume
+    }
+
     pub fn get_sample(&mut self) -> f32 {
         let sample = self.lerp();
         self.index += self.index_increment;
@@ -58,3 +69,9 @@ impl LFO {
             + next_index_weight * self.wave_table[next_index]
     }
 }
+
+impl Default for LFO {
+    fn default() -> Self {
+        Self::new(DEFAULT_SAMPLE_RATE)
+    }
+}
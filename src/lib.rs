@@ -1,42 +1,109 @@
-use rodio::source::Source;
-use std::{
-    sync::{Arc, Mutex},
-    time::Duration,
+use event::{EventReceiver, SynthHandle};
+use record::RecordTap;
+use rodio::{
+    cpal::{self, traits::HostTrait},
+    source::Source,
+    DeviceTrait,
 };
+use std::time::Duration;
 use synth::Synth;
 
+pub mod bank;
+pub mod cc_map;
 pub mod chorus;
 pub mod env;
+pub mod event;
 pub mod lfo;
+pub mod midi;
+pub mod mipmap;
 pub mod moog_filter;
 pub mod osc;
+pub mod patch;
+pub mod record;
+pub mod render;
 pub mod reverb;
 pub mod synth;
+pub mod table_builder;
+pub mod voice;
+pub mod wav_table;
 
-pub const SAMPLE_RATE: u32 = 48_000;
+/// the sample rate used when there's no output device to ask, and for offline rendering
+pub const DEFAULT_SAMPLE_RATE: u32 = 48_000;
+/// how many frames are rendered at a time, new events are applied between blocks. (1.3 ms)
+pub const BLOCK_SIZE: usize = 64;
+/// the synth plays in stereo, frames are interleaved (left, right)
+pub const CHANNELS: u16 = 2;
 
+/// plays a synth on the audio thread. the synth is owned here, other threads change it through
+/// the `SynthHandle` made with it.
 pub struct Player {
-    pub synth: Arc<Mutex<Synth>>,
+    pub synth: Synth,
+    events: EventReceiver,
+    /// the block being played, as interleaved frames
+    block: [f32; BLOCK_SIZE * CHANNELS as usize],
+    /// samples played since the last block boundary
+    block_i: usize,
+    /// where the output is copied to when recording
+    pub tap: Option<RecordTap>,
+}
+
+impl Player {
+    pub fn new(synth: Synth) -> (Self, SynthHandle) {
+        let (handle, events) = event::queue(&synth);
+
+        (
+            Self {
+                synth,
+                events,
+                block: [0.0; BLOCK_SIZE * CHANNELS as usize],
+                block_i: 0,
+                tap: None,
+            },
+            handle,
+        )
+    }
+
+    /// the sample rate of the default output device, which is what `OutputStream::try_default`
+    /// opens it at. make the synth with this so rodio doesn't have to resample it.
+    pub fn device_sample_rate() -> u32 {
+        cpal::default_host()
+            .default_output_device()
+            .and_then(|device| device.default_output_config().ok())
+            .map(|config| config.sample_rate().0)
+            .unwrap_or(DEFAULT_SAMPLE_RATE)
+    }
 }
 
 impl Iterator for Player {
     type Item = f32;
 
     fn next(&mut self) -> Option<Self::Item> {
-        // println!("yet to lock");
-        let sample = self.synth.lock().expect("couldn't lock synth").get_sample();
-        // println!("locked");
+        if self.block_i == 0 {
+            self.events.apply(&mut self.synth);
+            self.synth.render_stereo(&mut self.block);
+        }
+
+        // whole frames are recorded, as their left sample is played
+        if let Some(tap) = self.tap.as_mut() {
+            if self.block_i.is_multiple_of(CHANNELS as usize) {
+                tap.push(self.block[self.block_i], self.block[self.block_i + 1]);
+            }
+        }
+
+        let sample = self.block[self.block_i];
+        self.block_i = (self.block_i + 1) % self.block.len();
+
         Some(sample)
     }
 }
 
 impl Source for Player {
     fn channels(&self) -> u16 {
-        1
+        CHANNELS
     }
 
     fn sample_rate(&self) -> u32 {
-        SAMPLE_RATE
+        self.synth.sample_rate
     }
 
     fn current_frame_len(&self) -> Option<usize> {
@@ -0,0 +1,205 @@
+use crate::{
+    bank::Bank,
+    cc_map::CcMap,
+    event::{Event, SynthHandle},
+    synth::{Param, Synth},
+};
+use anyhow::{bail, Result};
+use midi_control::{ControlEvent, KeyEvent, MidiMessage};
+use std::{
+    num::ParseIntError,
+    sync::{Arc, Mutex},
+};
+
+/// CC numbers that are handled by the router itself rather than mapped to a parameter.
+const ALL_SOUND_OFF: u8 = 120;
+const ALL_NOTES_OFF: u8 = 123;
+const SUSTAIN_PEDAL: u8 = 64;
+const SOSTENUTO_PEDAL: u8 = 66;
+const SOFT_PEDAL: u8 = 67;
+
+/// the CC map used when there's no CC map file. the filter and modulation envelope stages are on
+/// CCs the MIDI spec leaves undefined.
+pub const DEFAULT_CC_MAP: [(u8, Param); 21] = [
+    (1, Param::LeslieSpeed),
+    (5, Param::Glide),
+    (7, Param::Volume),
+    (70, Param::Attack),
+    (71, Param::Decay),
+    (72, Param::Sustain),
+    (73, Param::Release),
+    (74, Param::Cutoff),
+    (75, Param::Resonance),
+    (76, Param::ChorusDepth),
+    (77, Param::ChorusSpeed),
+    (78, Param::FilterEnvAmount),
+    (79, Param::ModEnvAmount),
+    (102, Param::FilterAttack),
+    (103, Param::FilterDecay),
+    (104, Param::FilterSustain),
+    (105, Param::FilterRelease),
+    (106, Param::ModAttack),
+    (107, Param::ModDecay),
+    (108, Param::ModSustain),
+    (109, Param::ModRelease),
+];
+
+/// routes incoming MIDI messages to a synth. owns the CC to parameter mapping and the preset bank
+/// used for program changes.
+#[derive(Clone, Debug)]
+pub struct MidiRouter {
+    pub cc_map: Arc<Mutex<CcMap>>,
+    /// when set, the next CC received is bound to this parameter. (MIDI learn)
+    pub learn: Arc<Mutex<Option<Param>>>,
+    pub bank: Option<Arc<Mutex<Bank>>>,
+}
+
+impl MidiRouter {
+    /// makes a router that uses the default CC map
+    pub fn new(bank: Option<Arc<Mutex<Bank>>>) -> Self {
+        Self::with_cc_map(CcMap::default(), bank)
+    }
+
+    pub fn with_cc_map(cc_map: CcMap, bank: Option<Arc<Mutex<Bank>>>) -> Self {
+        Self {
+            cc_map: Arc::new(Mutex::new(cc_map)),
+            learn: Arc::new(Mutex::new(None)),
+            bank,
+        }
+    }
+
+    /// makes a router that uses the CC map file, falling back to the default map if it can't be
+    /// read.
+    pub fn load_default(bank: Option<Arc<Mutex<Bank>>>) -> Self {
+        let cc_map = CcMap::load_default().unwrap_or_else(|e| {
+            println!("[ERROR] => couldn't load the CC map: {e}");
+            CcMap::default()
+        });
+
+        Self::with_cc_map(cc_map, bank)
+    }
+
+    /// binds the next CC received to `param`, or stops learning if `None`
+    pub fn learn(&self, param: Option<Param>) {
+        *self.learn.lock().unwrap() = param;
+    }
+
+    /// the parameter currently waiting for a CC to be bound to it
+    pub fn learning(&self) -> Option<Param> {
+        *self.learn.lock().unwrap()
+    }
+
+    /// binds `control` to the parameter being learned, if there is one, and saves the CC map.
+    fn bind_learned(&self, control: u8, channel: u8) {
+        let Some(param) = self.learn.lock().unwrap().take() else {
+            return;
+        };
+
+        let mut cc_map = self.cc_map.lock().unwrap();
+        cc_map.bind(control, Some(channel), param);
+        println!("learned => CC {control} on channel {channel} controls {param:?}");
+
+        if cc_map.path.is_some() {
+            if let Err(e) = cc_map.save() {
+                println!("[ERROR] => couldn't save the CC map: {e}");
+            }
+        }
+    }
+
+    /// queues `message` to be played by a running synth
+    pub fn handle(&self, synth: &SynthHandle, message: &MidiMessage) {
+        if let MidiMessage::Invalid = message {
+            println!("midi command invalid");
+            return;
+        }
+
+        if let Some(event) = self.event(message) {
+            synth.send(event);
+        }
+    }
+
+    /// parses raw MIDI bytes and queues them to be played by a running synth
+    pub fn handle_bytes(&self, synth: &SynthHandle, bytes: &[u8]) {
+        let message = MidiMessage::from(bytes);
+
+        if let MidiMessage::Invalid = message {
+            println!("midi_cmd -> {bytes:?}");
+        }
+
+        self.handle(synth, &message)
+    }
+
+    /// applies `message` straight to a synth, (for offline rendering)
+    pub fn apply(&self, synth: &mut Synth, message: &MidiMessage) {
+        if let Some(mut event) = self.event(message) {
+            event.apply(synth);
+        }
+    }
+
+    /// turns `message` into the event it plays. MIDI learn and program changes are handled here,
+    /// on the MIDI thread, so the audio thread only gets the result.
+    pub fn event(&self, message: &MidiMessage) -> Option<Event> {
+        match message {
+            MidiMessage::NoteOn(_, KeyEvent { key, value }) if *value > 0 => Some(Event::NoteOn {
+                note: *key,
+                velocity: *value,
+            }),
+            // a note on with a velocity of zero is a note off
+            MidiMessage::NoteOn(_, KeyEvent { key, value: _ })
+            | MidiMessage::NoteOff(_, KeyEvent { key, value: _ }) => Some(Event::NoteOff(*key)),
+            MidiMessage::PitchBend(_, lsb, msb) => Some(Event::PitchBend(pitch_bend(*lsb, *msb))),
+            MidiMessage::ControlChange(channel, ControlEvent { control, value }) => {
+                match *control {
+                    ALL_SOUND_OFF | ALL_NOTES_OFF => Some(Event::StopAll),
+                    // pedals are down from 64 up
+                    SUSTAIN_PEDAL => Some(Event::SustainPedal(*value >= 64)),
+                    SOSTENUTO_PEDAL => Some(Event::SostenutoPedal(*value >= 64)),
+                    SOFT_PEDAL => Some(Event::SoftPedal(*value >= 64)),
+                    control => {
+                        // channels are numbered 1 - 16 in the CC map
+                        let channel = *channel as u8 + 1;
+                        self.bind_learned(control, channel);
+
+                        let binding = self.cc_map.lock().unwrap().find(control, channel).copied();
+
+                        binding.map(|binding| Event::Param(binding.param, binding.scale(*value)))
+                    }
+                }
+            }
+            MidiMessage::ProgramChange(_, program) => {
+                self.bank
+                    .as_ref()?
+                    .lock()
+                    .unwrap()
+                    .program_change(*program)
+                    .map(|patch| Event::Patch(Box::new(patch)))
+            }
+            MidiMessage::PolyKeyPressure(..)
+            | MidiMessage::ChannelPressure(..)
+            | MidiMessage::SysEx(_)
+            | MidiMessage::Invalid => None,
+        }
+    }
+}
+
+/// converts the two bytes of a pitch bend message to a bend amount, (about -1.0..=1.0)
+pub fn pitch_bend(lsb: u8, msb: u8) -> f32 {
+    i16::from_le_bytes([lsb, msb]) as f32 / (32_000.0 * 0.5) - 1.0
+}
+
+/// decodes a line of hex sent by the serial MIDI controller into raw MIDI bytes
+pub fn decode_hex(s: &str) -> Result<Vec<u8>> {
+    let s = s.trim();
+    // println!("{s:#?}");
+
+    if !s.is_ascii() || !s.len().is_multiple_of(2) {
+        bail!("not a whole number of hex bytes: {s:?}");
+    }
+
+    let bytes: Result<Vec<u8>, ParseIntError> = (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16))
+        .collect();
+
+    Ok(bytes?)
+}
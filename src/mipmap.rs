@@ -0,0 +1,110 @@
+use crate::synth::{WaveTable, WAVE_TABLE_SIZE};
+use std::{f64::consts::PI, sync::Arc};
+
+/// how many band limited copies of a wave table are kept, one per octave.
+pub const MIP_LEVELS: usize = 8;
+
+/// a wave table with a band limited copy for each octave. level `n` only has the first
+/// `WAVE_TABLE_SIZE / 2 >> n` harmonics, so it can be played `2^n` times faster than the table's
+/// base frequency without aliasing.
+#[derive(Clone, PartialEq, PartialOrd, Debug)]
+pub struct MipMap {
+    levels: Arc<[WaveTable]>,
+}
+
+impl MipMap {
+    /// builds the mip levels of a single cycle wave table by throwing away harmonics in the
+    /// frequency domain. `table` must be `WAVE_TABLE_SIZE` samples long.
+    pub fn new(table: &[f32]) -> Self {
+        let twiddles = twiddles(table.len());
+        let spectrum = dft(table, &twiddles);
+
+        let levels = (0..MIP_LEVELS)
+            .map(|level| {
+                // the nyquist bin is dropped so the top level doesn't alias at the table's base
+                // frequency.
+                let harmonics = ((WAVE_TABLE_SIZE / 2) >> level).min(WAVE_TABLE_SIZE / 2 - 1);
+
+                inverse_dft(&spectrum, &twiddles, harmonics)
+            })
+            .collect();
+
+        Self { levels }
+    }
+
+    /// the table with every harmonic, what the wave looks like
+    pub fn table(&self) -> &WaveTable {
+        &self.levels[0]
+    }
+
+    pub fn level(&self, level: usize) -> &WaveTable {
+        &self.levels[level.min(MIP_LEVELS - 1)]
+    }
+
+    /// which level to read, and how much of the next level to mix in, for a wave table
+    /// oscillator that steps through the table `index_increment` samples at a time. the levels
+    /// are picked so that neither has harmonics above nyquist.
+    pub fn pick_level(index_increment: f32) -> (usize, f32) {
+        if index_increment <= 0.0 {
+            return (0, 0.0);
+        }
+
+        let octave = index_increment.log2();
+        let level = octave.ceil().max(0.0);
+        let mix = (octave - (level - 1.0)).clamp(0.0, 1.0);
+        let level = level as usize;
+
+        if level >= MIP_LEVELS - 1 {
+            (MIP_LEVELS - 1, 0.0)
+        } else {
+            (level, mix)
+        }
+    }
+}
+
+/// `(cos, sin)` of every phase step of an `n` point DFT
+fn twiddles(n: usize) -> Vec<(f64, f64)> {
+    (0..n)
+        .map(|i| {
+            let phase = 2.0 * PI * i as f64 / n as f64;
+            (phase.cos(), phase.sin())
+        })
+        .collect()
+}
+
+/// the complex spectrum of a table, as (real, imaginary) pairs.
+fn dft(table: &[f32], twiddles: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let n = table.len();
+
+    (0..n)
+        .map(|k| {
+            table
+                .iter()
+                .enumerate()
+                .fold((0.0, 0.0), |(re, im), (i, sample)| {
+                    let (cos, sin) = twiddles[(k * i) % n];
+                    (re + *sample as f64 * cos, im - *sample as f64 * sin)
+                })
+        })
+        .collect()
+}
+
+/// rebuilds a table from the DC offset and first `harmonics` harmonics of its spectrum.
+fn inverse_dft(spectrum: &[(f64, f64)], twiddles: &[(f64, f64)], harmonics: usize) -> WaveTable {
+    let n = spectrum.len();
+
+    (0..n)
+        .map(|i| {
+            let dc = spectrum[0].0 / n as f64;
+
+            let sample = (1..=harmonics).fold(dc, |sample, k| {
+                let (re, im) = spectrum[k];
+                let (cos, sin) = twiddles[(k * i) % n];
+
+                sample + 2.0 * (re * cos - im * sin) / n as f64
+            });
+
+            sample as f32
+        })
+        .collect()
+}
@@ -1,4 +1,4 @@
-use crate::SAMPLE_RATE;
+use crate::DEFAULT_SAMPLE_RATE;
 use num_traits::clamp;
 use std::f32::consts::PI;
 
@@ -22,7 +22,7 @@ pub struct HuovilainenMoog {
 const THERMAL: f32 = 0.000025f32;
 
 impl HuovilainenMoog {
-    pub fn new() -> Self {
+    pub fn new(sample_rate: u32) -> Self {
         let mut filter = Self {
             stage: [0.0; 4],
             stage_tanh: [0.0; 3],
@@ -32,7 +32,7 @@ impl HuovilainenMoog {
             res_quad: 0.0,
             coeff_cutoff: 0.0,
             coeff_resonance: 0.0,
-            sample_rate: SAMPLE_RATE as f32,
+            sample_rate: sample_rate as f32,
         };
 
         filter.compute_coeffs(5_000.0, 0.75);
@@ -96,24 +96,33 @@ impl HuovilainenMoog {
             self.delay[5] = (self.stage[3] + self.delay[4]) * 0.5;
             self.delay[4] = self.stage[3];
         }
-        self.delay[5] as f32
+        self.delay[5]
+    }
+}
+
+impl Default for HuovilainenMoog {
+    fn default() -> Self {
+        Self::new(DEFAULT_SAMPLE_RATE)
     }
 }
 
 #[derive(Clone, Copy, Debug)]
 pub struct LowPass {
     filter: HuovilainenMoog,
+    /// the right channel's filter, only used by `get_frame`
+    filter_r: HuovilainenMoog,
     cutoff: f32,
     resonance: f32,
 }
 
 impl LowPass {
-    pub fn new() -> Self {
-        let mut filter = HuovilainenMoog::new();
+    pub fn new(sample_rate: u32) -> Self {
+        let mut filter = HuovilainenMoog::new(sample_rate);
         filter.compute_coeffs(5_000.0, 0.75);
 
         Self {
             filter,
+            filter_r: filter,
             cutoff: 5_000.0,
             resonance: 0.75,
         }
@@ -127,9 +136,50 @@ impl LowPass {
         self.resonance = res;
     }
 
-    pub fn get_sample(&mut self, sample: f32, env: f32) -> f32 {
-        self.filter
-            .process(sample, self.cutoff * env, self.resonance * env)
+    pub fn cutoff(&self) -> f32 {
+        self.cutoff
+    }
+
+    pub fn resonance(&self) -> f32 {
+        self.resonance
+    }
+
+    /// the cutoff and resonance scaled by `cutoff_mod` and `resonance_mod`
+    fn modulated(&self, cutoff_mod: f32, resonance_mod: f32) -> (f32, f32) {
+        (
+            self.cutoff * cutoff_mod,
+            (self.resonance * resonance_mod).min(1.0),
+        )
+    }
+
+    /// filters a sample. `cutoff_mod` and `resonance_mod` scale the cutoff and resonance, (set
+    /// from the filter envelope, velocity and modulation envelope)
+    pub fn get_sample(&mut self, sample: f32, cutoff_mod: f32, resonance_mod: f32) -> f32 {
+        let (cutoff, resonance) = self.modulated(cutoff_mod, resonance_mod);
+
+        self.filter.process(sample, cutoff, resonance)
+    }
+
+    /// filters a stereo frame, both channels with the same settings
+    pub fn get_frame(
+        &mut self,
+        left: f32,
+        right: f32,
+        cutoff_mod: f32,
+        resonance_mod: f32,
+    ) -> (f32, f32) {
+        let (cutoff, resonance) = self.modulated(cutoff_mod, resonance_mod);
+
+        (
+            self.filter.process(left, cutoff, resonance),
+            self.filter_r.process(right, cutoff, resonance),
+        )
+    }
+}
+
+impl Default for LowPass {
+    fn default() -> Self {
+        Self::new(DEFAULT_SAMPLE_RATE)
     }
 }
 
@@ -1,18 +1,163 @@
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
 use crate::{
-    env::ADSR,
+    env::{Envelope, ModTarget, ADSR},
+    mipmap::MipMap,
     moog_filter::LowPass,
-    synth::{WaveTable, WAVE_TABLE_SIZE},
-    SAMPLE_RATE,
+    synth::{pan_gains, WAVE_TABLE_SIZE},
+    DEFAULT_SAMPLE_RATE,
 };
 
+/// how long a stolen voice takes to fade out before its new note starts, in seconds
+const STEAL_FADE: f32 = 0.005;
+/// how many octaves the modulation envelope moves the pitch at full amount
+const MOD_PITCH_OCTAVES: f32 = 1.0;
+/// how many octaves the modulation envelope moves the cutoff at full amount
+const MOD_CUTOFF_OCTAVES: f32 = 4.0;
+/// the most copies of a note unison can stack
+pub const MAX_UNISON: usize = 8;
+/// the most unison copies a bank can play at once, summed over all of its voices. the Surface RT's
+/// ARM CPU only keeps up with a few, a desktop can play every voice at `MAX_UNISON`.
+#[cfg(target_arch = "arm")]
+pub const UNISON_BUDGET: usize = 32;
+#[cfg(not(target_arch = "arm"))]
+pub const UNISON_BUDGET: usize = crate::voice::MAX_POLYPHONY * MAX_UNISON;
+
+/// detuned copies of each note played by an oscillator, for thick pads and supersaws
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Unison {
+    /// how many copies of each note play, (1..=MAX_UNISON)
+    pub voices: usize,
+    /// how far apart the highest and lowest copies are tuned, in cents
+    pub detune: f32,
+    /// how far the copies are fanned out across the stereo field, (0.0..=1.0)
+    pub width: f32,
+}
+
+impl Unison {
+    /// where copy `i` sits between the lowest and highest copy, (-1.0..=1.0)
+    pub fn position(&self, i: usize) -> f32 {
+        if self.voices < 2 {
+            0.0
+        } else {
+            i as f32 / (self.voices - 1) as f32 * 2.0 - 1.0
+        }
+    }
+
+    /// true if the copies are spread out across the stereo field
+    pub fn is_stereo(&self) -> bool {
+        self.voices > 1 && self.width > 0.0
+    }
+}
+
+impl Default for Unison {
+    fn default() -> Self {
+        Self {
+            voices: 1,
+            detune: 20.0,
+            width: 0.5,
+        }
+    }
+}
+
+/// a note waiting for a stolen voice to fade out
 #[derive(Clone, Copy, Debug)]
+struct PendingNote {
+    midi_note: u8,
+    velocity: f32,
+    /// the key was let go before the note started
+    released: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
 pub struct Overtone {
     /// the frequency of the overtone relative to the fundimental
     pub overtone: f64,
     /// how loud this over tone is relative to the total volume (ie, 1.0)
     pub volume: f64,
+    /// how the ratio is edited
+    #[serde(default)]
+    pub mode: RatioMode,
+}
+
+/// the lowest and highest overtone ratio
+pub const OVERTONE_RANGE: (f64, f64) = (1.0 / 16.0, 32.0);
+
+/// how an overtone's ratio to the fundimental is edited
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum RatioMode {
+    /// any ratio
+    #[default]
+    Free,
+    /// ratios are snapped to whole semitones
+    Semitone,
+    /// ratios are snapped to harmonics, (2, 3, 4...) or subharmonics, (1/2, 1/3, 1/4...)
+    Harmonic,
+}
+
+impl RatioMode {
+    /// cycles through the modes, used by the overtone mode button
+    pub fn next(&self) -> Self {
+        match self {
+            Self::Free => Self::Semitone,
+            Self::Semitone => Self::Harmonic,
+            Self::Harmonic => Self::Free,
+        }
+    }
+
+    /// snaps a ratio to the nearest ratio allowed in this mode
+    pub fn snap(&self, ratio: f64) -> f64 {
+        let ratio = ratio.clamp(OVERTONE_RANGE.0, OVERTONE_RANGE.1);
+
+        match self {
+            Self::Free => ratio,
+            Self::Semitone => 2.0_f64.powf((ratio.log2() * 12.0).round() / 12.0),
+            Self::Harmonic if ratio >= 1.0 => ratio.round(),
+            Self::Harmonic => 1.0 / (1.0 / ratio).round(),
+        }
+    }
+
+    /// the next ratio up or down from `ratio`. (10 cents, a semitone or a harmonic)
+    pub fn step(&self, ratio: f64, up: bool) -> f64 {
+        let ratio = self.snap(ratio);
+        let dir = if up { 1.0 } else { -1.0 };
+
+        let stepped = match self {
+            Self::Free => ratio * 2.0_f64.powf(dir * 10.0 / 1200.0),
+            Self::Semitone => ratio * 2.0_f64.powf(dir / 12.0),
+            // harmonics count up, subharmonics count down, they meet at 1
+            Self::Harmonic if ratio > 1.0 || (ratio == 1.0 && up) => ratio + dir,
+            Self::Harmonic => 1.0 / (1.0 / ratio - dir),
+        };
+
+        self.snap(stepped)
+    }
+}
+
+/// where an oscillator reads from a multi-frame wave table, (0.0 is the first frame and 1.0 the
+/// last) and how far the LFO and modulation envelope move it.
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct TablePosition {
+    pub position: f32,
+    pub lfo: f32,
+    pub env: f32,
+}
+
+impl TablePosition {
+    /// the modulated position, `shift` moves it on top of the LFO and envelope
+    pub fn at(&self, lfo: f32, env: f32, shift: f32) -> f32 {
+        (self.position + self.lfo * lfo + self.env * env + shift).clamp(0.0, 1.0)
+    }
+}
+
+/// one of the wave tables mixed together by an oscillator
+#[derive(Clone, Debug)]
+pub struct TableMix {
+    /// the frames of the table, single cycle tables only have one
+    pub frames: Arc<[MipMap]>,
+    pub volume: f32,
+    pub position: TablePosition,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -20,26 +165,61 @@ pub struct WavetableOscillator {
     sample_rate: f32,
     index: f32,
     index_increment: f32,
+    /// the band limited level of the wave table read at this frequency
+    mip_level: usize,
+    /// how much of the next level up is mixed in
+    mip_mix: f32,
 }
 
 impl WavetableOscillator {
-    pub fn new() -> Self {
+    pub fn new(sample_rate: u32) -> Self {
         Self {
-            sample_rate: SAMPLE_RATE as f32,
+            sample_rate: sample_rate as f32,
             index: 0.0,
             index_increment: 0.0,
+            mip_level: 0,
+            mip_mix: 0.0,
         }
     }
 
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// where in the cycle the oscillator is, (0.0..1.0)
+    pub fn phase(&self) -> f32 {
+        self.index / WAVE_TABLE_SIZE as f32
+    }
+
+    /// jumps to `phase` in the cycle, (0.0..1.0)
+    pub fn set_phase(&mut self, phase: f32) {
+        self.index = phase.rem_euclid(1.0) * WAVE_TABLE_SIZE as f32;
+    }
+
     pub fn set_frequency(&mut self, frequency: f32) {
         self.index_increment = frequency * WAVE_TABLE_SIZE as f32 / self.sample_rate;
+        (self.mip_level, self.mip_mix) = MipMap::pick_level(self.index_increment);
     }
 
-    pub fn get_sample(&mut self, wave_tables: &Arc<[(WaveTable, f32)]>) -> f32 {
+    /// mixes a sample of each table, `lfo`, `env` and `shift` move the position in multi-frame
+    /// tables.
+    pub fn get_sample(&mut self, wave_tables: &[TableMix], lfo: f32, env: f32, shift: f32) -> f32 {
         let mut sample = 0.0;
 
-        for (table, weight) in wave_tables.iter() {
-            sample += self.lerp(table) * weight;
+        for table in wave_tables.iter() {
+            let frames = &table.frames;
+            let position = table.position.at(lfo, env, shift) * (frames.len() - 1) as f32;
+            let frame = position as usize;
+            let frame_mix = position - frame as f32;
+
+            let mut level = self.read(&frames[frame]);
+
+            if frame_mix > 0.0 && frame + 1 < frames.len() {
+                let next = self.read(&frames[frame + 1]);
+                level += (next - level) * frame_mix;
+            }
+
+            sample += level * table.volume;
         }
 
         self.index += self.index_increment;
@@ -48,6 +228,18 @@ impl WavetableOscillator {
         sample
     }
 
+    /// reads a frame, crossfading between the band limited levels for the current frequency
+    fn read(&self, table: &MipMap) -> f32 {
+        let level = self.lerp(table.level(self.mip_level));
+
+        if self.mip_mix > 0.0 {
+            let next = self.lerp(table.level(self.mip_level + 1));
+            level + (next - level) * self.mip_mix
+        } else {
+            level
+        }
+    }
+
     fn lerp(&self, wave_table: &[f32]) -> f32 {
         let truncated_index = self.index as usize;
         let next_index = (truncated_index + 1) % WAVE_TABLE_SIZE;
@@ -60,42 +252,269 @@ impl WavetableOscillator {
     }
 }
 
+impl Default for WavetableOscillator {
+    fn default() -> Self {
+        Self::new(DEFAULT_SAMPLE_RATE)
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct Oscillator {
-    wt_osc: WavetableOscillator,
+    /// one wave table oscillator per unison copy, only the first `unison.voices` play
+    wt_osc: [WavetableOscillator; MAX_UNISON],
+    unison: Unison,
+    /// how much each unison copy's frequency is scaled by
+    unison_ratio: [f32; MAX_UNISON],
+    /// the state of the random number generator that picks the unison copies' phases
+    seed: u32,
     pub env_filter: ADSR,
+    /// sweeps the low pass filter's cutoff and resonance
+    pub filter_env: ADSR,
+    /// how far `filter_env` sweeps the filter, (-1.0..=1.0). positive amounts open the filter up
+    /// to the cutoff as the envelope rises, negative ones close it down from the cutoff.
+    pub filter_env_amount: f32,
+    pub mod_env: ADSR,
+    pub mod_target: ModTarget,
+    /// how far `mod_env` moves `mod_target`, (-1.0..=1.0)
+    pub mod_amount: f32,
+    /// how much the modulation envelope scales the frequency by
+    pitch_mod: f32,
+    /// how far the modulation envelope moves the wave tables' positions
+    table_mod: f32,
     /// what midi note is being played by this osc
     pub playing: Option<u8>,
     frequency: f32,
     base_frequency: f32,
+    /// how much the pitch bend wheel scales `base_frequency` by
+    bend_ratio: f32,
+    /// how much `base_frequency` is multiplied by each sample while gliding
+    glide_step: f32,
+    /// how many samples are left in the current glide
+    glide_left: u32,
+    glide_target: f32,
+    /// how much every note's frequency is scaled by, set from its bank's octave and fine tune
+    tuning: f32,
     note_space: f32,
     pub low_pass: LowPass,
+    /// the level of the note being played, set from its velocity
+    velocity: f32,
+    /// how much the velocity scales the filter cutoff, (0.0 leaves the cutoff alone)
+    pub velocity_cutoff: f32,
+    /// how many samples are left in the fade out of a stolen voice
+    fade: u32,
+    pending: Option<PendingNote>,
 }
 
 impl Oscillator {
-    pub fn new() -> Self {
+    pub fn new(sample_rate: u32) -> Self {
         Self {
-            wt_osc: WavetableOscillator::new(),
-            env_filter: ADSR::new(),
+            wt_osc: [WavetableOscillator::new(sample_rate); MAX_UNISON],
+            unison: Unison::default(),
+            unison_ratio: [1.0; MAX_UNISON],
+            seed: 0x2545_f491,
+            env_filter: ADSR::new(sample_rate),
+            filter_env: ADSR::new(sample_rate),
+            filter_env_amount: 1.0,
+            mod_env: ADSR::new(sample_rate),
+            mod_target: ModTarget::default(),
+            mod_amount: 0.0,
+            pitch_mod: 1.0,
+            table_mod: 0.0,
             playing: None,
             frequency: 0.0,
             base_frequency: 0.0,
+            bend_ratio: 1.0,
+            glide_step: 1.0,
+            glide_left: 0,
+            glide_target: 0.0,
+            tuning: 1.0,
             note_space: 2.0_f32.powf(1.0 / 12.0),
-            low_pass: LowPass::new(),
+            low_pass: LowPass::new(sample_rate),
+            velocity: 1.0,
+            velocity_cutoff: 0.0,
+            fade: 0,
+            pending: None,
         }
     }
 
     pub fn is_pressed(&self) -> bool {
-        self.env_filter.pressed()
+        self.pending.is_some_and(|note| !note.released) || self.env_filter.pressed()
+    }
+
+    /// stops playing immediately
+    pub fn reset(&mut self) {
+        self.reset_envelopes();
+        self.playing = None;
+        self.fade = 0;
+        self.pending = None;
+    }
+
+    /// the current envelope level
+    pub fn level(&self) -> f32 {
+        self.env_filter.level()
+    }
+
+    pub fn envelope(&self, envelope: Envelope) -> &ADSR {
+        match envelope {
+            Envelope::Amp => &self.env_filter,
+            Envelope::Filter => &self.filter_env,
+            Envelope::Mod => &self.mod_env,
+        }
     }
 
-    pub fn press(&mut self, midi_note: u8) {
+    pub fn envelope_mut(&mut self, envelope: Envelope) -> &mut ADSR {
+        match envelope {
+            Envelope::Amp => &mut self.env_filter,
+            Envelope::Filter => &mut self.filter_env,
+            Envelope::Mod => &mut self.mod_env,
+        }
+    }
+
+    fn press_envelopes(&mut self) {
         self.env_filter.press();
-        self.frequency = Self::get_freq(midi_note);
-        self.base_frequency = self.frequency;
+        self.filter_env.press();
+        self.mod_env.press();
+    }
 
-        self.wt_osc.set_frequency(self.frequency);
+    fn reset_envelopes(&mut self) {
+        self.env_filter.reset();
+        self.filter_env.reset();
+        self.mod_env.reset();
+        self.pitch_mod = 1.0;
+    }
+
+    /// quickly fades out whatever is playing then starts playing `midi_note`. used when a voice is
+    /// stolen to avoid a click.
+    pub fn steal(&mut self, midi_note: u8, velocity: f32) {
+        self.fade = (STEAL_FADE * self.wt_osc[0].sample_rate()) as u32;
+        self.pending = Some(PendingNote {
+            midi_note,
+            velocity,
+            released: false,
+        });
+    }
+
+    /// starts playing `midi_note` at `velocity`, (0.0..=1.0)
+    pub fn press(&mut self, midi_note: u8, velocity: f32) {
+        self.press_envelopes();
+        self.velocity = velocity;
+        self.fade = 0;
+        self.pending = None;
+        self.glide_left = 0;
+        self.bend_ratio = 1.0;
+        self.base_frequency = Self::get_freq(midi_note) * self.tuning;
+        self.frequency = self.base_frequency;
+
+        self.set_wt_frequency(self.frequency);
         self.playing = Some(midi_note);
+
+        // copies that start in phase sound like one loud oscillator until they drift apart
+        if self.unison.voices > 1 {
+            self.seed ^= (midi_note as u32 + 1).wrapping_mul(0x9e37_79b9);
+
+            for i in 0..self.unison.voices {
+                let phase = self.random() as f32 / u32::MAX as f32;
+                self.wt_osc[i].set_phase(phase);
+            }
+        }
+    }
+
+    /// a random number from a xorshift generator, so picking phases never locks or allocates
+    fn random(&mut self) -> u32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+
+        self.seed
+    }
+
+    /// sets how many detuned copies of each note play, and how they're spread out. takes effect
+    /// right away, even on a note that's playing.
+    pub fn set_unison(&mut self, unison: Unison) {
+        self.unison = Unison {
+            voices: unison.voices.clamp(1, MAX_UNISON),
+            ..unison
+        };
+
+        for i in 0..MAX_UNISON {
+            let cents = self.unison.detune * 0.5 * self.unison.position(i);
+            self.unison_ratio[i] = 2.0_f32.powf(cents / 1_200.0);
+        }
+
+        self.set_wt_frequency(self.frequency);
+    }
+
+    pub fn unison(&self) -> Unison {
+        self.unison
+    }
+
+    /// where unison copy `copy` is in its cycle, (0.0..1.0)
+    pub fn phase(&self, copy: usize) -> f32 {
+        self.wt_osc[copy].phase()
+    }
+
+    /// sets the frequency of every unison copy, detuned around `frequency`
+    fn set_wt_frequency(&mut self, frequency: f32) {
+        for i in 0..self.unison.voices {
+            self.wt_osc[i].set_frequency(frequency * self.unison_ratio[i]);
+        }
+    }
+
+    /// the frequency being played, before vibrato
+    pub fn frequency(&self) -> f32 {
+        self.frequency
+    }
+
+    /// sets how much every note's frequency is scaled by. a note that's playing is retuned right
+    /// away.
+    pub fn set_tuning(&mut self, tuning: f32) {
+        let change = tuning / self.tuning;
+        self.tuning = tuning;
+
+        self.base_frequency *= change;
+        self.glide_target *= change;
+        self.frequency = self.base_frequency * self.bend_ratio;
+        self.set_wt_frequency(self.frequency);
+    }
+
+    pub fn tuning(&self) -> f32 {
+        self.tuning
+    }
+
+    /// restarts the envelope without changing the pitch
+    pub fn retrigger(&mut self, velocity: f32) {
+        self.press_envelopes();
+        self.velocity = velocity;
+    }
+
+    /// slides the pitch to `midi_note` over `samples` samples. (portamento)
+    pub fn glide(&mut self, midi_note: u8, samples: f32) {
+        self.glide_target = Self::get_freq(midi_note) * self.tuning;
+
+        if samples < 1.0 || self.base_frequency <= 0.0 {
+            self.glide_left = 0;
+            self.base_frequency = self.glide_target;
+            self.frequency = self.base_frequency * self.bend_ratio;
+            self.set_wt_frequency(self.frequency);
+            return;
+        }
+
+        self.glide_step = (self.glide_target / self.base_frequency).powf(1.0 / samples);
+        self.glide_left = samples as u32;
+    }
+
+    fn step_glide(&mut self) {
+        if self.glide_left == 0 {
+            return;
+        }
+
+        self.glide_left -= 1;
+        self.base_frequency = if self.glide_left == 0 {
+            self.glide_target
+        } else {
+            self.base_frequency * self.glide_step
+        };
+        self.frequency = self.base_frequency * self.bend_ratio;
     }
 
     fn get_freq(midi_note: u8) -> f32 {
@@ -106,44 +525,155 @@ impl Oscillator {
     }
 
     pub fn release(&mut self) {
+        if let Some(note) = self.pending.as_mut() {
+            note.released = true;
+            return;
+        }
+
         self.env_filter.release();
+        self.filter_env.release();
+        self.mod_env.release();
         // self.playing = None;
     }
 
-    pub fn get_sample(&mut self, wave_table: &Arc<[(WaveTable, f32)]>) -> f32 {
+    /// plays the next frame, the unison copies are summed into the left and right channels with
+    /// `gains`. (one pair per copy)
+    fn get_frame(
+        &mut self,
+        wave_table: &[TableMix],
+        lfo: f32,
+        gains: &[(f32, f32); MAX_UNISON],
+    ) -> (f32, f32) {
         let env = self.env_filter.get_samnple();
-        let sample = self.wt_osc.get_sample(wave_table) * env;
+        let sweep = filter_sweep(self.filter_env.get_samnple(), self.filter_env_amount);
+        let mod_level = self.mod_env.get_samnple();
+        let modulation = mod_level * self.mod_amount;
+        let stereo = self.unison.is_stereo();
+        let (mut left, mut right) = (0.0, 0.0);
 
-        if env <= 0.0 {
+        for (wt_osc, (gain_l, gain_r)) in self.wt_osc[..self.unison.voices].iter_mut().zip(gains) {
+            let sample = wt_osc.get_sample(wave_table, lfo, mod_level, self.table_mod);
+
+            if stereo {
+                left += sample * gain_l;
+                right += sample * gain_r;
+            } else {
+                left += sample;
+            }
+        }
+
+        // keeps the level about the same however many copies play
+        let mut level = env * self.velocity / (self.unison.voices as f32).sqrt();
+
+        if let Some(note) = self.pending {
+            let fade_len = (STEAL_FADE * self.wt_osc[0].sample_rate()) as u32;
+            level *= self.fade as f32 / fade_len as f32;
+            self.fade = self.fade.saturating_sub(1);
+
+            if self.fade == 0 {
+                // keep the key the synth gave this voice
+                let playing = self.playing;
+                self.pending = None;
+                self.reset_envelopes();
+                self.press(note.midi_note, note.velocity);
+                self.playing = playing;
+
+                if note.released {
+                    self.release();
+                }
+            }
+        } else if env <= 0.0 {
             self.playing = None;
         }
-        // println!("osc sample => {sample}");
 
-        self.low_pass.get_sample(sample, env)
+        self.step_glide();
+
+        let mut cutoff_mod = (1.0 - self.velocity_cutoff * (1.0 - self.velocity)) * sweep;
+        let mut resonance_mod = sweep;
+        // the pitch is picked up by the next call to `vibrato` and the table position by the next
+        // frame
+        self.pitch_mod = 1.0;
+        self.table_mod = 0.0;
+
+        if modulation != 0.0 {
+            match self.mod_target {
+                ModTarget::Pitch => self.pitch_mod = 2.0_f32.powf(modulation * MOD_PITCH_OCTAVES),
+                ModTarget::Cutoff => cutoff_mod *= 2.0_f32.powf(modulation * MOD_CUTOFF_OCTAVES),
+                ModTarget::Resonance => resonance_mod *= 2.0_f32.powf(modulation),
+                ModTarget::TablePosition => self.table_mod = modulation,
+            }
+        }
+
+        if stereo {
+            self.low_pass
+                .get_frame(left * level, right * level, cutoff_mod, resonance_mod)
+        } else {
+            let sample = self
+                .low_pass
+                .get_sample(left * level, cutoff_mod, resonance_mod);
+            (sample, sample)
+        }
+    }
+
+    /// adds this oscillator's next `left.len()` frames to `left` and `right`, stopping early if
+    /// the note ends. `lfo` holds the LFO's sample for each one, the note is panned to `pan` with
+    /// its unison copies fanned out around it.
+    pub fn render(
+        &mut self,
+        wave_tables: &[TableMix],
+        lfo: &[f32],
+        pan: f32,
+        left: &mut [f32],
+        right: &mut [f32],
+    ) {
+        let gains = if self.unison.is_stereo() {
+            std::array::from_fn(|i| pan_gains(pan + self.unison.width * self.unison.position(i)))
+        } else {
+            [(1.0, 1.0); MAX_UNISON]
+        };
+        // copies that aren't spread out are panned together, after the filter
+        let (gain_l, gain_r) = if self.unison.is_stereo() {
+            (1.0, 1.0)
+        } else {
+            pan_gains(pan)
+        };
+
+        for ((left, right), lfo) in left.iter_mut().zip(right.iter_mut()).zip(lfo) {
+            if self.playing.is_none() {
+                break;
+            }
+
+            self.vibrato(*lfo);
+            let (l, r) = self.get_frame(wave_tables, *lfo, &gains);
+            *left += l * gain_l;
+            *right += r * gain_r;
+        }
     }
 
     pub fn vibrato(&mut self, amt: f32) {
         let amt = amt * 0.25;
+        let frequency = self.frequency * self.pitch_mod;
 
         let next_note = if amt > 0.0 {
-            self.frequency * self.note_space
+            frequency * self.note_space
         } else if amt == 0.0 {
-            self.wt_osc.set_frequency(self.frequency);
+            self.set_wt_frequency(frequency);
             return;
         } else {
-            self.frequency / self.note_space
+            frequency / self.note_space
         };
 
-        let freq_delta = (self.frequency - next_note).abs();
+        let freq_delta = (frequency - next_note).abs();
         let adjust_amt = freq_delta * amt * 0.5;
-        self.wt_osc.set_frequency(self.frequency + adjust_amt)
+        self.set_wt_frequency(frequency + adjust_amt)
     }
 
     pub fn bend(&mut self, bend: f32) {
         // println!("bending");
-        let new_freq = self.base_frequency * 2.0_f32.powf((bend * 3.0) / 12.0);
+        self.bend_ratio = 2.0_f32.powf((bend * 3.0) / 12.0);
+        let new_freq = self.base_frequency * self.bend_ratio;
         // + self.frequency;
-        self.wt_osc.set_frequency(new_freq);
+        self.set_wt_frequency(new_freq);
         // println!("frequency => {}", self.frequency);
         // println!("new_freq => {}", new_freq);
         self.frequency = new_freq;
@@ -151,7 +681,23 @@ impl Oscillator {
 
     pub fn unbend(&mut self) {
         // println!("unbend => {}", self.base_frequency);
-        self.wt_osc.set_frequency(self.base_frequency);
+        self.bend_ratio = 1.0;
+        self.set_wt_frequency(self.base_frequency);
         self.frequency = self.base_frequency;
     }
 }
+
+/// how much the filter envelope at `level` scales the cutoff by, for an `amount` in -1.0..=1.0
+pub fn filter_sweep(level: f32, amount: f32) -> f32 {
+    if amount >= 0.0 {
+        1.0 - amount * (1.0 - level)
+    } else {
+        1.0 + amount * level
+    }
+}
+
+impl Default for Oscillator {
+    fn default() -> Self {
+        Self::new(DEFAULT_SAMPLE_RATE)
+    }
+}
@@ -0,0 +1,236 @@
+use crate::{
+    env::{Curve, ModTarget, Stage, ADSR},
+    osc::{Overtone, TablePosition, Unison},
+    synth::VelocityCurve,
+    voice::{GlideMode, NotePriority, StealPolicy, VoiceMode, DEFAULT_POLYPHONY},
+};
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path, path::PathBuf, sync::Arc};
+
+/// every user adjustable parameter of a `Synth`. values are stored in the same units the matching
+/// `Synth::set_*` method takes.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Patch {
+    /// master volume
+    pub volume: f32,
+    pub oscillators: [OscPatch; 3],
+    pub overtones: [Overtone; 10],
+    pub envelope: EnvelopePatch,
+    /// the filter's own envelope, patches saved before it existed sweep the filter with the amp
+    /// envelope
+    #[serde(default)]
+    pub filter_envelope: Option<FilterEnvelopePatch>,
+    #[serde(default)]
+    pub mod_envelope: Option<ModEnvelopePatch>,
+    pub filter: FilterPatch,
+    pub lfo: LfoPatch,
+    pub chorus: ChorusPatch,
+    pub reverb: ReverbPatch,
+    #[serde(default)]
+    pub voices: VoicePatch,
+    #[serde(default)]
+    pub velocity: VelocityPatch,
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct OscPatch {
+    pub wave: Wave,
+    pub volume: f32,
+    /// detune in semitones, (-12..=12)
+    pub detune: i16,
+    /// octave shift, (-3..=3)
+    #[serde(default)]
+    pub octave: i8,
+    /// fine tune in cents, (-100.0..=100.0)
+    #[serde(default)]
+    pub fine_tune: f32,
+    #[serde(default)]
+    pub unison: Unison,
+    /// where multi-frame wave tables are read from
+    #[serde(default)]
+    pub position: TablePosition,
+    /// where the bank sits in the stereo field, (-1.0 is left, 1.0 is right)
+    #[serde(default)]
+    pub pan: f32,
+    /// how far the bank's voices are fanned out around `pan`, (0.0..=1.0)
+    #[serde(default)]
+    pub spread: f32,
+    /// true if the bank is switched off
+    #[serde(default)]
+    pub muted: bool,
+    #[serde(default)]
+    pub solo: bool,
+}
+
+/// the wave form of an oscillator. custom wave tables are saved by name rather than by their
+/// `OscType::Custom` id, which changes when WAV files are added or renamed.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Wave {
+    Sin,
+    Tri,
+    Sqr,
+    Saw,
+    /// shares the loaded table's name, so snapshots taken on the audio thread don't allocate
+    Custom(Arc<str>),
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct EnvelopePatch {
+    /// attack time in seconds
+    pub attack: f32,
+    /// decay time in seconds
+    pub decay: f32,
+    /// sustain level (0.0..=1.0)
+    pub sustain: f32,
+    pub release: f32,
+    #[serde(default)]
+    pub attack_curve: Curve,
+    #[serde(default)]
+    pub decay_curve: Curve,
+    #[serde(default)]
+    pub release_curve: Curve,
+}
+
+impl From<&ADSR> for EnvelopePatch {
+    fn from(env: &ADSR) -> Self {
+        Self {
+            attack: env.atk(),
+            decay: env.decay(),
+            sustain: env.sus(),
+            release: env.release_time(),
+            attack_curve: env.curve(Stage::Attack),
+            decay_curve: env.curve(Stage::Decay),
+            release_curve: env.curve(Stage::Release),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct FilterEnvelopePatch {
+    pub envelope: EnvelopePatch,
+    /// how far the envelope sweeps the cutoff, (-1.0..=1.0)
+    pub amount: f32,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ModEnvelopePatch {
+    pub envelope: EnvelopePatch,
+    pub target: ModTarget,
+    /// how far the envelope moves `target`, (-1.0..=1.0)
+    pub amount: f32,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct FilterPatch {
+    /// low pass cutoff (0.0..=1.0), scaled to 0 - 10 kHz
+    pub cutoff: f32,
+    pub resonance: f32,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct LfoPatch {
+    /// frequency in Hz
+    pub frequency: f32,
+    pub volume: f32,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ChorusPatch {
+    pub power: bool,
+    pub volume: f32,
+    pub speed: f32,
+    /// stereo width (0.0..=1.0), patches saved before the synth was stereo play it in mono
+    #[serde(default)]
+    pub width: f32,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ReverbPatch {
+    pub power: bool,
+    pub gain: f32,
+    pub decay: f32,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct VoicePatch {
+    /// how many notes can play at once
+    pub polyphony: usize,
+    /// which voice is cut off when they're all busy
+    pub stealing: StealPolicy,
+    #[serde(default)]
+    pub mode: VoiceMode,
+    /// which held key plays in mono and legato mode
+    #[serde(default)]
+    pub priority: NotePriority,
+    /// glide time in seconds
+    #[serde(default)]
+    pub glide: f32,
+    #[serde(default)]
+    pub glide_mode: GlideMode,
+}
+
+impl Default for VoicePatch {
+    fn default() -> Self {
+        Self {
+            polyphony: DEFAULT_POLYPHONY,
+            stealing: StealPolicy::default(),
+            mode: VoiceMode::default(),
+            priority: NotePriority::default(),
+            glide: 0.0,
+            glide_mode: GlideMode::default(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct VelocityPatch {
+    pub curve: VelocityCurve,
+    /// how much soft notes close the low pass filter, (0.0..=1.0)
+    pub cutoff: f32,
+}
+
+impl Patch {
+    /// reads a patch from a TOML file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let patch = toml::from_str(&fs::read_to_string(path)?)?;
+
+        Ok(patch)
+    }
+
+    /// writes the patch to a TOML file, creating the parent directory if needed
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        fs::write(path, toml::to_string_pretty(self)?)?;
+
+        Ok(())
+    }
+
+    /// where the patch that was last used is kept between runs.
+    /// (`~/.config/synth-rt/patch.toml` on linux)
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("synth-rt").join("patch.toml"))
+    }
+
+    /// loads the patch at `default_path`, if one was saved
+    pub fn load_default() -> Result<Option<Self>> {
+        match Self::default_path() {
+            Some(path) if path.exists() => Ok(Some(Self::load(path)?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// saves the patch to `default_path`
+    pub fn save_default(&self) -> Result<()> {
+        let Some(path) = Self::default_path() else {
+            bail!("couldn't find a config directory to save the patch to");
+        };
+
+        self.save(path)
+    }
+}
@@ -0,0 +1,182 @@
+use crate::CHANNELS;
+use anyhow::{bail, Result};
+use chrono::Local;
+use hound::{SampleFormat, WavSpec, WavWriter};
+use rtrb::{Consumer, Producer, RingBuffer};
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{sleep, spawn, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// how many seconds of audio the ring buffer between the audio thread and the writer thread can
+/// hold
+const RING_BUFFER_SECONDS: usize = 2;
+
+/// the audio thread's end of a recorder. it copies samples into the ring buffer while recording.
+pub struct RecordTap {
+    producer: Producer<f32>,
+    recording: Arc<AtomicBool>,
+}
+
+impl RecordTap {
+    /// tees a frame into the recording. never blocks, if the writer thread falls behind the whole
+    /// frame is dropped so the channels stay in step.
+    pub fn push(&mut self, left: f32, right: f32) {
+        if self.recording.load(Ordering::Relaxed) && self.producer.slots() >= CHANNELS as usize {
+            let _ = self.producer.push(left);
+            let _ = self.producer.push(right);
+        }
+    }
+}
+
+/// the UI's end of a recorder. drains the ring buffer into a timestamped WAV file on a writer
+/// thread.
+pub struct Recorder {
+    /// the directory recordings are saved to
+    pub dir: PathBuf,
+    pub sample_rate: u32,
+    recording: Arc<AtomicBool>,
+    consumer: Option<Consumer<f32>>,
+    writer: Option<JoinHandle<(Consumer<f32>, Result<()>)>>,
+    file: Option<PathBuf>,
+    started: Option<Instant>,
+}
+
+impl Recorder {
+    /// makes a recorder that saves `sample_rate` Hz audio into `dir`, and the tap that feeds it
+    /// samples.
+    pub fn new(dir: impl Into<PathBuf>, sample_rate: u32) -> (Self, RecordTap) {
+        let (producer, consumer) =
+            RingBuffer::new(sample_rate as usize * CHANNELS as usize * RING_BUFFER_SECONDS);
+        let recording = Arc::new(AtomicBool::new(false));
+
+        let recorder = Self {
+            dir: dir.into(),
+            sample_rate,
+            recording: recording.clone(),
+            consumer: Some(consumer),
+            writer: None,
+            file: None,
+            started: None,
+        };
+
+        (
+            recorder,
+            RecordTap {
+                producer,
+                recording,
+            },
+        )
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.load(Ordering::Relaxed)
+    }
+
+    /// how long the current recording has been going for
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.started.map(|started| started.elapsed())
+    }
+
+    /// starts writing samples to a new WAV file, returns the path of that file.
+    pub fn start(&mut self) -> Result<PathBuf> {
+        let Some(mut consumer) = self.consumer.take() else {
+            bail!("already recording");
+        };
+
+        // throw away anything left over from the last recording.
+        if let Ok(chunk) = consumer.read_chunk(consumer.slots()) {
+            chunk.commit_all();
+        }
+
+        fs::create_dir_all(&self.dir)?;
+        let file = self.dir.join(format!(
+            "synth-rt-{}.wav",
+            Local::now().format("%Y-%m-%d_%H-%M-%S")
+        ));
+
+        let spec = WavSpec {
+            channels: CHANNELS,
+            sample_rate: self.sample_rate,
+            bits_per_sample: 32,
+            sample_format: SampleFormat::Float,
+        };
+
+        let mut writer = match WavWriter::create(&file, spec) {
+            Ok(writer) => writer,
+            Err(e) => {
+                self.consumer = Some(consumer);
+                bail!(e);
+            }
+        };
+
+        self.recording.store(true, Ordering::Relaxed);
+        let recording = self.recording.clone();
+
+        self.writer = Some(spawn(move || {
+            let write = || -> Result<()> {
+                loop {
+                    let still_recording = recording.load(Ordering::Relaxed);
+                    let chunk = consumer.read_chunk(consumer.slots())?;
+
+                    if chunk.is_empty() {
+                        if !still_recording {
+                            break;
+                        }
+
+                        sleep(Duration::from_millis(10));
+                        continue;
+                    }
+
+                    let (first, second) = chunk.as_slices();
+
+                    for sample in first.iter().chain(second) {
+                        writer.write_sample(*sample)?;
+                    }
+
+                    chunk.commit_all();
+                }
+
+                writer.finalize()?;
+
+                Ok(())
+            };
+
+            let res = write();
+
+            (consumer, res)
+        }));
+
+        self.file = Some(file.clone());
+        self.started = Some(Instant::now());
+
+        Ok(file)
+    }
+
+    /// stops recording and waits for the WAV file to be written. returns the path of that file.
+    pub fn stop(&mut self) -> Result<PathBuf> {
+        let Some(writer) = self.writer.take() else {
+            bail!("not recording");
+        };
+
+        self.recording.store(false, Ordering::Relaxed);
+        self.started = None;
+
+        let Ok((consumer, res)) = writer.join() else {
+            bail!("the recording writer thread panicked");
+        };
+
+        self.consumer = Some(consumer);
+        res?;
+
+        self.file
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("recording had no file"))
+    }
+}
@@ -0,0 +1,215 @@
+use crate::{
+    midi::MidiRouter,
+    synth::{Synth, MAX_BLOCK},
+    CHANNELS,
+};
+use anyhow::{bail, Result};
+use hound::{SampleFormat, WavSpec, WavWriter};
+use midly::{
+    num::{u4, u7},
+    MetaMessage, MidiMessage, Smf, Timing, TrackEventKind,
+};
+use std::{fs, path::Path};
+
+/// default tempo of a standard MIDI file when no tempo meta event is given (120 bpm).
+const DEFAULT_TEMPO: u32 = 500_000;
+
+/// the sample format of the rendered WAV file
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BitDepth {
+    Int16,
+    Int24,
+    Float32,
+}
+
+impl BitDepth {
+    pub fn from_bits(bits: u16) -> Option<Self> {
+        match bits {
+            16 => Some(Self::Int16),
+            24 => Some(Self::Int24),
+            32 => Some(Self::Float32),
+            _ => None,
+        }
+    }
+
+    fn spec(&self, sample_rate: u32) -> WavSpec {
+        let (bits_per_sample, sample_format) = match self {
+            Self::Int16 => (16, SampleFormat::Int),
+            Self::Int24 => (24, SampleFormat::Int),
+            Self::Float32 => (32, SampleFormat::Float),
+        };
+
+        WavSpec {
+            channels: CHANNELS,
+            sample_rate,
+            bits_per_sample,
+            sample_format,
+        }
+    }
+}
+
+/// a channel MIDI message and the sample it should be played at
+#[derive(Clone, Copy, Debug)]
+pub struct TimedEvent {
+    /// the sample offset, from the start of the render, of the event
+    pub sample: u64,
+    pub channel: u4,
+    pub message: MidiMessage,
+}
+
+/// reads a standard MIDI file and returns all of its channel messages, from every track, ordered
+/// by the sample they should be played at when rendering at `sample_rate` Hz.
+pub fn load_midi_file(path: impl AsRef<Path>, sample_rate: u32) -> Result<Vec<TimedEvent>> {
+    let bytes = fs::read(path)?;
+    let smf = Smf::parse(&bytes)?;
+
+    // merge all tracks into one list of (tick, event) so that tempo changes in the first track
+    // apply to the notes in the others.
+    let mut events = Vec::new();
+
+    for track in smf.tracks.iter() {
+        let mut tick: u64 = 0;
+
+        for event in track {
+            tick += event.delta.as_int() as u64;
+            events.push((tick, event.kind));
+        }
+    }
+
+    events.sort_by_key(|(tick, _kind)| *tick);
+
+    let mut tempo = DEFAULT_TEMPO;
+    let mut last_tick = 0;
+    let mut seconds = 0.0_f64;
+    let mut timed = Vec::with_capacity(events.len());
+
+    for (tick, kind) in events {
+        let ticks = (tick - last_tick) as f64;
+        last_tick = tick;
+
+        seconds += match smf.header.timing {
+            Timing::Metrical(tpb) => ticks * tempo as f64 / (tpb.as_int() as f64 * 1_000_000.0),
+            Timing::Timecode(fps, sub_frame) => ticks / (fps.as_f32() as f64 * sub_frame as f64),
+        };
+
+        match kind {
+            TrackEventKind::Meta(MetaMessage::Tempo(t)) => tempo = t.as_int(),
+            TrackEventKind::Midi { channel, message } => timed.push(TimedEvent {
+                sample: (seconds * sample_rate as f64).round() as u64,
+                channel,
+                message,
+            }),
+            _ => {}
+        }
+    }
+
+    Ok(timed)
+}
+
+/// converts a MIDI file message to the message type the live MIDI inputs produce
+fn to_live_message(channel: u4, message: MidiMessage) -> midi_control::MidiMessage {
+    use midi_control::{Channel, ControlEvent, KeyEvent, MidiMessage as Live};
+
+    let channel = Channel::from(channel.as_int());
+    let key_event = |key: u7, value: u7| KeyEvent {
+        key: key.as_int(),
+        value: value.as_int(),
+    };
+
+    match message {
+        MidiMessage::NoteOn { key, vel } => Live::NoteOn(channel, key_event(key, vel)),
+        MidiMessage::NoteOff { key, vel } => Live::NoteOff(channel, key_event(key, vel)),
+        MidiMessage::Aftertouch { key, vel } => Live::PolyKeyPressure(channel, key_event(key, vel)),
+        MidiMessage::Controller { controller, value } => Live::ControlChange(
+            channel,
+            ControlEvent {
+                control: controller.as_int(),
+                value: value.as_int(),
+            },
+        ),
+        MidiMessage::ProgramChange { program } => Live::ProgramChange(channel, program.as_int()),
+        MidiMessage::ChannelAftertouch { vel } => Live::ChannelPressure(channel, vel.as_int()),
+        MidiMessage::PitchBend { bend } => {
+            // split into the same 7 bit halves the live MIDI inputs receive.
+            let raw = bend.0.as_int();
+            Live::PitchBend(channel, (raw & 0x7F) as u8, (raw >> 7) as u8)
+        }
+    }
+}
+
+/// plays `events` through `synth`, sample accurately, handing every generated sample to `sink` as
+/// interleaved stereo frames, (left, right, left, right...)
+/// keeps rendering for `tail` seconds after the last event so that releases can ring out.
+pub fn render_events(
+    synth: &mut Synth,
+    router: &MidiRouter,
+    events: &[TimedEvent],
+    tail: f32,
+    mut sink: impl FnMut(f32) -> Result<()>,
+) -> Result<()> {
+    let mut sample_i: u64 = 0;
+    // offline there's no audio thread to keep fed, so wait for any table rebuilds (from a patch
+    // or a program change) to play the same thing on every render.
+    synth.wait_for_tables();
+
+    let mut block = [0.0; MAX_BLOCK * CHANNELS as usize];
+
+    for event in events {
+        while sample_i < event.sample {
+            let len = (event.sample - sample_i).min(MAX_BLOCK as u64) as usize;
+            let frames = &mut block[..len * CHANNELS as usize];
+            synth.render_stereo(frames);
+            frames.iter().try_for_each(|sample| sink(*sample))?;
+            sample_i += len as u64;
+        }
+
+        router.apply(synth, &to_live_message(event.channel, event.message));
+        synth.wait_for_tables();
+    }
+
+    let mut tail = (tail.max(0.0) * synth.sample_rate as f32) as u64;
+
+    while tail > 0 {
+        let len = tail.min(MAX_BLOCK as u64) as usize;
+        let frames = &mut block[..len * CHANNELS as usize];
+        synth.render_stereo(frames);
+        frames.iter().try_for_each(|sample| sink(*sample))?;
+        tail -= len as u64;
+    }
+
+    Ok(())
+}
+
+/// renders the MIDI file at `midi_path` through `synth` into a stereo WAV file at `wav_path`.
+pub fn render_midi_file(
+    synth: &mut Synth,
+    router: &MidiRouter,
+    midi_path: impl AsRef<Path>,
+    wav_path: impl AsRef<Path>,
+    bit_depth: BitDepth,
+    tail: f32,
+) -> Result<()> {
+    let events = load_midi_file(midi_path, synth.sample_rate)?;
+
+    if events.is_empty() {
+        bail!("the MIDI file has no channel events to render");
+    }
+
+    let mut writer = WavWriter::create(wav_path, bit_depth.spec(synth.sample_rate))?;
+
+    render_events(synth, router, &events, tail, |sample| {
+        let sample = sample.clamp(-1.0, 1.0);
+
+        match bit_depth {
+            BitDepth::Int16 => writer.write_sample((sample * i16::MAX as f32) as i16)?,
+            BitDepth::Int24 => writer.write_sample((sample * 8_388_607.0) as i32)?,
+            BitDepth::Float32 => writer.write_sample(sample)?,
+        }
+
+        Ok(())
+    })?;
+
+    writer.finalize()?;
+
+    Ok(())
+}
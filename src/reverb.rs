@@ -1,27 +1,73 @@
+use crate::DEFAULT_SAMPLE_RATE;
 use reverb;
 
+/// how far the right plate's input trails the left's, so a centered sound comes back wide, in
+/// seconds
+const RIGHT_DELAY: f32 = 0.01;
+
+/// a stereo reverb, one plate per channel
 pub struct Reverb {
-    pub effect: reverb::Reverb,
+    pub left: reverb::Reverb,
+    pub right: reverb::Reverb,
+    /// the right plate's input, `RIGHT_DELAY` seconds behind
+    delay: Vec<f32>,
+    delay_i: usize,
     pub gain: f32,
     pub decay: f32,
     pub power: bool,
 }
 
 impl Reverb {
-    pub fn new() -> Self {
+    pub fn new(sample_rate: u32) -> Self {
+        let delay = ((sample_rate as f32 * RIGHT_DELAY) as usize).max(1);
+
         Self {
-            effect: reverb::Reverb::new(),
+            left: reverb::Reverb::new(),
+            right: reverb::Reverb::new(),
+            delay: vec![0.0; delay],
+            delay_i: 0,
             gain: 0.5,
             decay: 0.5,
             power: false,
         }
     }
 
-    pub fn get_sample(&mut self, in_sample: f32) -> f32 {
+    pub fn get_frame(&mut self, left: f32, right: f32) -> (f32, f32) {
         if self.power {
-            self.effect.calc_sample(in_sample, self.gain)
+            let right = self.delay_right(right);
+
+            (
+                self.left.calc_sample(left, self.gain),
+                self.right.calc_sample(right, self.gain),
+            )
         } else {
-            in_sample
+            (left, right)
+        }
+    }
+
+    /// pushes a sample into the right plate's delay, returns the one that comes out
+    fn delay_right(&mut self, sample: f32) -> f32 {
+        let delayed = std::mem::replace(&mut self.delay[self.delay_i], sample);
+        self.delay_i = (self.delay_i + 1) % self.delay.len();
+
+        delayed
+    }
+
+    /// runs every frame of `left` and `right` through the reverb, into `out_l` and `out_r`
+    pub fn process(&mut self, left: &[f32], right: &[f32], out_l: &mut [f32], out_r: &mut [f32]) {
+        if !self.power {
+            out_l.copy_from_slice(left);
+            out_r.copy_from_slice(right);
+            return;
+        }
+
+        for (out, sample) in out_l.iter_mut().zip(left) {
+            *out = self.left.calc_sample(*sample, self.gain);
+        }
+
+        for (out, sample) in out_r.iter_mut().zip(right) {
+            let sample = self.delay_right(*sample);
+            *out = self.right.calc_sample(sample, self.gain);
         }
     }
 
@@ -32,7 +78,8 @@ impl Reverb {
     pub fn set_decay(&mut self, decay: f32) {
         self.decay = decay;
 
-        self.effect = self.effect.decay(decay).clone();
+        self.left.decay(decay);
+        self.right.decay(decay);
     }
 
     pub fn turn_power_on(&mut self, power: bool) {
@@ -43,3 +90,9 @@ impl Reverb {
         self.power = !self.power;
     }
 }
+
+impl Default for Reverb {
+    fn default() -> Self {
+        Self::new(DEFAULT_SAMPLE_RATE)
+    }
+}
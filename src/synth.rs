@@ -1,41 +1,208 @@
 use crate::{
     chorus::Chorus,
+    env::{Curve, Envelope, ModTarget, Stage, ADSR},
     lfo::LFO,
-    osc::{Oscillator, Overtone},
+    mipmap::MipMap,
+    osc::{
+        Oscillator, Overtone, RatioMode, TableMix, TablePosition, Unison, MAX_UNISON, UNISON_BUDGET,
+    },
+    patch::{
+        ChorusPatch, EnvelopePatch, FilterEnvelopePatch, FilterPatch, LfoPatch, ModEnvelopePatch,
+        OscPatch, Patch, ReverbPatch, VelocityPatch, VoicePatch, Wave,
+    },
     reverb::Reverb,
+    table_builder::TableBuilder,
+    voice::{
+        Allocation, GlideMode, NotePriority, VoiceAllocator, VoiceMode, DEFAULT_POLYPHONY,
+        MAX_POLYPHONY,
+    },
+    wav_table::WavTable,
+    DEFAULT_SAMPLE_RATE,
 };
+use anyhow::{bail, Result};
+use arc_swap::{ArcSwap, Guard};
 use midi_control::MidiNote;
-use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use std::{
+    f32::consts::{FRAC_1_SQRT_2, FRAC_PI_4},
+    sync::Arc,
+};
 
 pub type WaveTable = Arc<[f32]>;
 // pub type WaveTables = [(WaveTable, f32); 2];
 
 pub const WAVE_TABLE_SIZE: usize = 256;
-pub const VOICES: usize = 10;
+/// how much the soft pedal scales the level of new notes
+pub const SOFT_PEDAL_LEVEL: f32 = 0.6;
+/// the most frames `Synth::render` processes at once, longer buffers are split into blocks of this
+/// many frames.
+pub const MAX_BLOCK: usize = 256;
 
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
 pub enum OscType {
     Sin,
     Tri,
     Sqr,
     Saw,
+    /// a wave table imported from a WAV file, the id is its index in `WaveTables::custom`. patches
+    /// save the table's name instead, see `Wave`.
+    Custom(usize),
+}
+
+/// how the velocity of a note is mapped to its level
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum VelocityCurve {
+    #[default]
+    Linear,
+    /// soft notes are quieter, gives more control at the bottom of the range
+    Exponential,
+    /// every note is played at full level
+    Fixed,
+}
+
+impl VelocityCurve {
+    /// converts a MIDI velocity to a level in 0.0..=1.0
+    pub fn apply(&self, velocity: u8) -> f32 {
+        let velocity = velocity.min(127) as f32 / 127.0;
+
+        match self {
+            Self::Linear => velocity,
+            Self::Exponential => velocity * velocity,
+            Self::Fixed => 1.0,
+        }
+    }
+}
+
+/// a continuous synth parameter that can be set from a MIDI CC. takes a value in 0.0..=1.0
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Param {
+    Volume,
+    Attack,
+    Decay,
+    Sustain,
+    Release,
+    Cutoff,
+    Resonance,
+    ChorusDepth,
+    ChorusSpeed,
+    LeslieSpeed,
+    ReverbGain,
+    ReverbDecay,
+    VelocityCutoff,
+    /// glide time, (0 - 1 seconds)
+    Glide,
+    /// the volume of one of the three oscillators
+    OscVolume(usize),
+    /// where one of the three oscillators reads from multi-frame wave tables
+    TablePosition(usize),
+    /// the volume of one of the ten overtones
+    OvertoneVolume(usize),
+    /// the fine tune of one of the three oscillator banks, (0.0 is -100 cents, 1.0 is +100)
+    FineTune(usize),
+    /// how far apart one of the three banks tunes its unison copies, (0 - 100 cents)
+    UnisonDetune(usize),
+    /// how far one of the three banks fans its unison copies out
+    UnisonWidth(usize),
+    /// where one of the three oscillator banks sits, (0.0 is left, 1.0 is right)
+    OscPan(usize),
+    /// how far one of the three oscillator banks fans its voices out
+    OscSpread(usize),
+    ChorusWidth,
+    FilterAttack,
+    FilterDecay,
+    FilterSustain,
+    FilterRelease,
+    /// how far the filter envelope sweeps the cutoff, (0.0 is -1.0, 1.0 is +1.0)
+    FilterEnvAmount,
+    ModAttack,
+    ModDecay,
+    ModSustain,
+    ModRelease,
+    /// how far the modulation envelope moves its target, (0.0 is -1.0, 1.0 is +1.0)
+    ModEnvAmount,
+}
+
+/// equal power gains for the left and right channels, for a sound at `pan`. (-1.0 is left, 1.0
+/// is right)
+pub fn pan_gains(pan: f32) -> (f32, f32) {
+    let angle = (pan.clamp(-1.0, 1.0) + 1.0) * FRAC_PI_4;
+
+    (angle.cos(), angle.sin())
+}
+
+/// where `voice` of a bank of `voices` sits. the voices are fanned out evenly from left to right
+/// of `pan`, `spread` sets how far.
+pub fn voice_pan(pan: f32, spread: f32, voice: usize, voices: usize) -> f32 {
+    if voices < 2 {
+        return pan;
+    }
+
+    let position = voice as f32 / (voices - 1) as f32 * 2.0 - 1.0;
+
+    (pan + spread * position).clamp(-1.0, 1.0)
 }
 
 #[derive(Clone, PartialEq, PartialOrd, Debug)]
 pub struct WaveTables {
-    pub sin: WaveTable,
-    pub tri: WaveTable,
-    pub sqr: WaveTable,
-    pub saw: WaveTable,
+    pub sin: Arc<[MipMap]>,
+    pub tri: Arc<[MipMap]>,
+    pub sqr: Arc<[MipMap]>,
+    pub saw: Arc<[MipMap]>,
+    /// wave tables imported from WAV files
+    pub custom: Arc<[WavTable]>,
 }
 
 impl WaveTables {
     pub fn new(overtones: &[Overtone]) -> Self {
         Self {
-            sin: Self::build_sine_table(overtones),
-            tri: Self::build_triangle_table(overtones),
-            sqr: Self::build_square_table(overtones),
-            saw: Self::build_saw_table(overtones),
+            sin: [MipMap::new(&Self::build_sine_table(overtones))].into(),
+            tri: [MipMap::new(&Self::build_triangle_table(overtones))].into(),
+            sqr: [MipMap::new(&Self::build_square_table(overtones))].into(),
+            saw: [MipMap::new(&Self::build_saw_table(overtones))].into(),
+            custom: Arc::new([]),
+        }
+    }
+
+    /// the frames played by an oscillator type
+    pub fn frames(&self, osc_type: OscType) -> &Arc<[MipMap]> {
+        match osc_type {
+            OscType::Sin => &self.sin,
+            OscType::Tri => &self.tri,
+            OscType::Sqr => &self.sqr,
+            OscType::Saw => &self.saw,
+            OscType::Custom(id) => &self.custom[id].frames,
+        }
+    }
+
+    /// the first frame played by an oscillator type
+    pub fn table(&self, osc_type: OscType) -> &MipMap {
+        &self.frames(osc_type)[0]
+    }
+
+    /// the oscillator type that plays a saved wave. custom tables are looked up by name.
+    pub fn osc_type(&self, wave: &Wave) -> Result<OscType> {
+        let osc_type = match wave {
+            Wave::Sin => OscType::Sin,
+            Wave::Tri => OscType::Tri,
+            Wave::Sqr => OscType::Sqr,
+            Wave::Saw => OscType::Saw,
+            Wave::Custom(name) => match self.custom.iter().position(|table| &table.name == name) {
+                Some(id) => OscType::Custom(id),
+                None => bail!("the wave table \"{name}\" isn't loaded"),
+            },
+        };
+
+        Ok(osc_type)
+    }
+
+    /// how an oscillator type is saved in a patch
+    pub fn wave(&self, osc_type: OscType) -> Wave {
+        match osc_type {
+            OscType::Sin => Wave::Sin,
+            OscType::Tri => Wave::Tri,
+            OscType::Sqr => Wave::Sqr,
+            OscType::Saw => Wave::Saw,
+            OscType::Custom(id) => Wave::Custom(self.custom[id].name.clone()),
         }
     }
 
@@ -46,16 +213,16 @@ impl WaveTables {
 
         let bias = 1.0 / n_overtones as f32;
 
-        for i in 0..WAVE_TABLE_SIZE {
+        for (i, sample) in wave_table.iter_mut().enumerate() {
             for ot in overtones {
                 // wave_table[i] += (((i as f64 % ot.overtone) - 1.0) * ot.volume) as f32
-                wave_table[i] +=
-                    ((((i as f64 * ((4.0 * ot.overtone) / WAVE_TABLE_SIZE as f64)) % 2.0) - 1.0)
-                        * ot.volume) as f32;
+                *sample += ((((i as f64 * ((4.0 * ot.overtone) / WAVE_TABLE_SIZE as f64)) % 2.0)
+                    - 1.0)
+                    * ot.volume) as f32;
                 // break;
             }
 
-            wave_table[i] *= bias;
+            *sample *= bias;
             // println!("saw tooth => {}", wave_table[i]);
         }
 
@@ -69,14 +236,14 @@ impl WaveTables {
 
         let bias = 1.0 / n_overtones as f32;
 
-        for i in 0..WAVE_TABLE_SIZE {
+        for (i, sample) in wave_table.iter_mut().enumerate() {
             for ot in overtones {
-                if (i as f64 % ot.overtone as f64) < 1.0 {
-                    wave_table[i] += ot.volume as f32
+                if (i as f64 % ot.overtone) < 1.0 {
+                    *sample += ot.volume as f32
                 }
             }
 
-            wave_table[i] *= bias;
+            *sample *= bias;
         }
 
         wave_table.into()
@@ -89,12 +256,12 @@ impl WaveTables {
 
         let bias = 1.0 / n_overtones as f32;
 
-        for i in 0..WAVE_TABLE_SIZE {
+        for (i, sample) in wave_table.iter_mut().enumerate() {
             for ot in overtones {
-                wave_table[i] += (((i as f64 % ot.overtone as f64) - 1.0).abs() * ot.volume) as f32
+                *sample += (((i as f64 % ot.overtone) - 1.0).abs() * ot.volume) as f32
             }
 
-            wave_table[i] *= bias;
+            *sample *= bias;
         }
 
         // println!("bigest build_triangle_table {:?}", wave_table.iter().max());
@@ -109,200 +276,602 @@ impl WaveTables {
 
         let bias = 1.0 / n_overtones as f32;
 
-        for i in 0..WAVE_TABLE_SIZE {
+        for (i, sample) in wave_table.iter_mut().enumerate() {
             for ot in overtones {
-                wave_table[i] += ((2.0 * core::f64::consts::PI * i as f64 * ot.overtone
+                *sample += ((2.0 * core::f64::consts::PI * i as f64 * ot.overtone
                     / WAVE_TABLE_SIZE as f64)
                     .sin()
                     * ot.volume) as f32
             }
 
-            wave_table[i] *= bias;
+            *sample *= bias;
         }
 
         wave_table.into()
     }
 
-    fn index(&self, index: &Arc<[(OscType, f32)]>) -> Arc<[(WaveTable, f32)]> {
-        index
-            .iter()
-            .map(|(osc_type, vol)| {
-                (
-                    match osc_type {
-                        OscType::Sin => self.sin.clone(),
-                        OscType::Tri => self.tri.clone(),
-                        OscType::Sqr => self.sqr.clone(),
-                        OscType::Saw => self.saw.clone(),
-                    },
-                    vol / index.len() as f32,
-                )
-            })
-            .collect()
+    /// the tables mixed by every oscillator. only clones `Arc`s, so it never allocates.
+    fn index(&self, index: &[(OscType, f32); 3], positions: &[TablePosition; 3]) -> [TableMix; 3] {
+        std::array::from_fn(|i| {
+            let (osc_type, vol) = index[i];
+
+            TableMix {
+                frames: self.frames(osc_type).clone(),
+                volume: vol / index.len() as f32,
+                position: positions[i],
+            }
+        })
     }
 }
 
 pub struct Synth {
-    pub osc_s: [([Oscillator; VOICES], i16); 3],
-    pub wave_tables: WaveTables,
+    /// the sample rate everything is played at, in Hz
+    pub sample_rate: u32,
+    /// the three oscillator banks, each with one oscillator per voice, and their detune in
+    /// semitones.
+    pub osc_s: [(Vec<Oscillator>, i16); 3],
+    /// how many octaves each bank is shifted by, (-3..=3) set with `set_octave`
+    pub octave: [i8; 3],
+    /// each bank's fine tune in cents, (-100.0..=100.0) set with `set_fine_tune`
+    pub fine_tune: [f32; 3],
+    /// how many detuned copies of each note every bank plays, set with `set_unison`
+    pub unison: [Unison; 3],
+    pub voices: VoiceAllocator,
+    pub voice_mode: VoiceMode,
+    pub note_priority: NotePriority,
+    /// glide time in seconds, (0.0 turns glide off) only used in mono and legato mode
+    pub glide: f32,
+    pub glide_mode: GlideMode,
+    /// the keys held down in mono and legato mode, in the order they were pressed
+    mono_keys: Vec<u8>,
+    /// the level of the last note played in mono and legato mode
+    mono_velocity: f32,
+    /// the tables being played, swapped atomically when `table_builder` finishes a rebuild
+    pub wave_tables: Arc<ArcSwap<WaveTables>>,
+    pub table_builder: TableBuilder,
     pub osc_type: [(OscType, f32); 3],
+    /// where each oscillator reads from multi-frame wave tables
+    pub table_position: [TablePosition; 3],
+    /// where each oscillator bank sits in the stereo field, (-1.0 is left, 1.0 is right)
+    pub pan: [f32; 3],
+    /// how far each bank's voices are fanned out around its pan, (0.0..=1.0) only used in poly
+    /// mode.
+    pub spread: [f32; 3],
+    /// banks that are switched off, set with `set_muted`
+    pub muted: [bool; 3],
+    /// banks that are soloed, set with `set_solo`. when any bank is soloed only those play.
+    pub solo: [bool; 3],
+    /// the tables the oscillators play, resolved from `osc_type` and `table_position`. only redone
+    /// when one of them or the wave tables change.
+    tables: [TableMix; 3],
+    /// what `tables` was resolved from
+    resolved_from: (Arc<WaveTables>, [(OscType, f32); 3], [TablePosition; 3]),
     pub overtones: [Overtone; 10],
     pub lfo: LFO,
     pub volume: f32,
     pub chorus: Chorus,
     pub reverb: Reverb,
+    pub velocity_curve: VelocityCurve,
+    /// true while the sustain (damper) pedal is down
+    pub sustain_pedal: bool,
+    /// true while the sostenuto pedal is down
+    pub sostenuto_pedal: bool,
+    /// true while the soft pedal is down
+    pub soft_pedal: bool,
+    /// notes whose key was let go while a pedal was holding them
+    held: [bool; 128],
+    /// notes that were down when the sostenuto pedal was pressed
+    sostenuto_notes: [bool; 128],
 }
 
 impl Synth {
-    pub fn new() -> Self {
+    /// makes a synth that plays at `sample_rate` Hz
+    pub fn new(sample_rate: u32) -> Self {
         let overtones = [
             Overtone {
                 overtone: 0.5_f64.powf(1.0 / 12.0),
                 volume: 1.0,
+                mode: RatioMode::Free,
             },
             Overtone {
                 // overtone: 2.0_f64.powf(1.0 / 12.0),
                 overtone: 1.5_f64.powf(1.0 / 12.0),
                 volume: 1.0,
+                mode: RatioMode::Free,
             },
             Overtone {
                 overtone: 1.0,
                 volume: 1.0,
+                mode: RatioMode::Free,
             },
             Overtone {
                 overtone: 3.0,
                 // overtone: 4.0,
                 volume: 1.0,
+                mode: RatioMode::Free,
             },
             Overtone {
                 overtone: 4.0,
                 // overtone: 8.0,
                 volume: 1.0,
+                mode: RatioMode::Free,
             },
             Overtone {
                 overtone: 5.0,
                 // overtone: 16.0,
                 volume: 1.0,
+                mode: RatioMode::Free,
             },
             Overtone {
                 overtone: 6.0,
                 // overtone: 32.0,
                 volume: 1.0,
+                mode: RatioMode::Free,
             },
             Overtone {
                 overtone: 8.0,
                 // overtone: 64.0,
                 volume: 1.0,
+                mode: RatioMode::Free,
             },
             Overtone {
                 overtone: 9.0,
                 // overtone: 128.0,
                 volume: 1.0,
+                mode: RatioMode::Free,
             },
             Overtone {
                 overtone: 10.0,
                 // overtone: 256.0,
                 volume: 1.0,
+                mode: RatioMode::Free,
             },
         ];
-        let wave_tables = WaveTables::new(&overtones);
-        let mut lfo = LFO::new();
+        let osc_type = [
+            // (OscType::Sin, 1.0),
+            (OscType::Saw, 1.0),
+            (OscType::Saw, 1.0),
+            (OscType::Saw, 1.0),
+            // (OscType::Tri, 0.75),
+            // (OscType::Sqr, 1.0),
+        ];
+        let table_position = [TablePosition::default(); 3];
+        let table_builder = TableBuilder::new(WaveTables::new(&overtones));
+        let wave_tables = table_builder.tables.load_full();
+        let mut lfo = LFO::new(sample_rate);
         lfo.set_frequency(400.0 / 60.0);
 
         Self {
-            osc_s: [([Oscillator::new(); VOICES], 0); 3],
-            wave_tables,
-            osc_type: [
-                // (OscType::Sin, 1.0),
-                (OscType::Saw, 1.0),
-                (OscType::Saw, 1.0),
-                (OscType::Saw, 1.0),
-                // (OscType::Tri, 0.75),
-                // (OscType::Sqr, 1.0),
-            ],
+            sample_rate,
+            // room for every voice up front, so changing the polyphony never allocates
+            osc_s: std::array::from_fn(|_| {
+                let mut voices = Vec::with_capacity(MAX_POLYPHONY);
+                voices.resize(DEFAULT_POLYPHONY, Oscillator::new(sample_rate));
+
+                (voices, 0)
+            }),
+            octave: [0; 3],
+            fine_tune: [0.0; 3],
+            unison: [Unison::default(); 3],
+            voices: VoiceAllocator::new(DEFAULT_POLYPHONY),
+            voice_mode: VoiceMode::default(),
+            note_priority: NotePriority::default(),
+            glide: 0.0,
+            glide_mode: GlideMode::default(),
+            mono_keys: Vec::with_capacity(128),
+            mono_velocity: 1.0,
+            wave_tables: table_builder.tables.clone(),
+            table_builder,
+            osc_type,
+            table_position,
+            pan: [0.0; 3],
+            spread: [0.0; 3],
+            muted: [false; 3],
+            solo: [false; 3],
+            tables: wave_tables.index(&osc_type, &table_position),
+            resolved_from: (wave_tables, osc_type, table_position),
             overtones,
             // osc_type: Arc::new([(OscType::Tri, 1.0)]),
             lfo,
             volume: 0.75,
-            chorus: Chorus::new(),
-            reverb: Reverb::new(),
+            chorus: Chorus::new(sample_rate),
+            reverb: Reverb::new(sample_rate),
+            velocity_curve: VelocityCurve::default(),
+            sustain_pedal: false,
+            sostenuto_pedal: false,
+            soft_pedal: false,
+            held: [false; 128],
+            sostenuto_notes: [false; 128],
         }
     }
 
+    /// rebuilds the wave tables from the overtones on the table builder thread. the old tables
+    /// keep playing until the new ones are swapped in.
     pub fn set_overtones(&mut self) {
-        self.wave_tables = WaveTables::new(&self.overtones);
+        self.table_builder.rebuild(self.overtones);
+    }
+
+    /// blocks until the tables from the last `set_overtones` are being played. for offline
+    /// rendering, where nothing else is playing while they're built.
+    pub fn wait_for_tables(&mut self) {
+        self.table_builder.flush();
     }
 
+    /// sets the wave tables that can be played with `OscType::Custom`. oscillators playing a custom
+    /// table are moved to the table of the same name in the new list.
+    pub fn set_wav_tables(&mut self, tables: impl Into<Arc<[WavTable]>>) {
+        let current = self.wave_tables.load_full();
+        let waves = self.osc_type.map(|(osc_type, _)| current.wave(osc_type));
+        self.table_builder.set_custom(tables.into());
+
+        for (i, wave) in waves.iter().enumerate() {
+            if let Err(e) = self.set_wave(i, wave) {
+                println!("[ERROR] => osc {}: {e}, playing a sine instead", i + 1);
+                self.osc_type[i].0 = OscType::Sin;
+            }
+        }
+    }
+
+    /// sets the wave form of oscillator `osc` from a patch
+    fn set_wave(&mut self, osc: usize, wave: &Wave) -> Result<()> {
+        self.osc_type[osc].0 = self.wave_tables.load().osc_type(wave)?;
+
+        Ok(())
+    }
+
+    /// plays one sample, mixed down to mono. `render` is much faster when playing more than one.
     pub fn get_sample(&mut self) -> f32 {
-        let mut sample = 0.0;
-        let lfo_sample = self.lfo.get_sample();
-        // println!("lfo sample {lfo_sample}");
+        let mut out = [0.0];
+        self.render(&mut out);
 
-        for (osc_s, _offset) in self.osc_s.iter_mut() {
-            // println!("{osc:?}");
-            for osc in osc_s {
-                if osc.playing.is_some() {
-                    // osc.for_each(|(osc, _offset)| {
-                    osc.vibrato(lfo_sample);
-                    // println!("playing");
-                    sample +=
-                        osc.get_sample(&self.wave_tables.index(&self.osc_type.clone().into()));
-                    // println!(
-                    //     "env => {}, {}",
-                    //     osc.env_filter.get_samnple(),
-                    //     osc.env_filter.phase
-                    // );
-                    // });
+        out[0]
+    }
+
+    /// fills `out` with the next `out.len()` samples, mixed down to mono. a sound panned to the
+    /// center plays at the same level as before it was panned.
+    pub fn render(&mut self, out: &mut [f32]) {
+        let (mut left, mut right) = ([0.0; MAX_BLOCK], [0.0; MAX_BLOCK]);
+
+        for block in out.chunks_mut(MAX_BLOCK) {
+            let len = block.len();
+            self.render_block(&mut left[..len], &mut right[..len]);
+
+            for ((sample, left), right) in block.iter_mut().zip(left).zip(right) {
+                *sample = (left + right) * FRAC_1_SQRT_2;
+            }
+        }
+    }
+
+    /// fills `out` with interleaved stereo frames, (left, right, left, right...)
+    pub fn render_stereo(&mut self, out: &mut [f32]) {
+        let (mut left, mut right) = ([0.0; MAX_BLOCK], [0.0; MAX_BLOCK]);
+
+        for frames in out.chunks_mut(MAX_BLOCK * 2) {
+            let len = frames.len() / 2;
+            self.render_block(&mut left[..len], &mut right[..len]);
+
+            for ((frame, left), right) in frames.chunks_exact_mut(2).zip(left).zip(right) {
+                frame[0] = left;
+                frame[1] = right;
+            }
+        }
+    }
+
+    /// looks the oscillators' tables up again if the wave forms, their positions or the wave
+    /// tables changed. the tables swapped out aren't freed here, the table builder frees them.
+    fn resolve_tables(&mut self) {
+        let wave_tables = self.wave_tables.load();
+        let (resolved, osc_type, table_position) = &self.resolved_from;
+
+        if Arc::ptr_eq(&wave_tables, resolved)
+            && *osc_type == self.osc_type
+            && *table_position == self.table_position
+        {
+            return;
+        }
+
+        self.tables = wave_tables.index(&self.osc_type, &self.table_position);
+        self.resolved_from = (
+            Guard::into_inner(wave_tables),
+            self.osc_type,
+            self.table_position,
+        );
+    }
+
+    /// renders up to `MAX_BLOCK` frames. the wave tables are looked up once for the whole block
+    /// and each oscillator runs through it in one go, instead of every oscillator once per sample.
+    /// every voice is panned on its own, then the effects run in stereo.
+    fn render_block(&mut self, left: &mut [f32], right: &mut [f32]) {
+        self.table_builder.send_pending();
+        let len = left.len();
+        let mut lfo = [0.0; MAX_BLOCK];
+        let lfo = &mut lfo[..len];
+        lfo.iter_mut()
+            .for_each(|sample| *sample = self.lfo.get_sample());
+
+        self.resolve_tables();
+        left.fill(0.0);
+        right.fill(0.0);
+
+        let spread = if self.voice_mode == VoiceMode::Poly {
+            self.spread
+        } else {
+            [0.0; 3]
+        };
+
+        let on = self.banks_on();
+
+        for (bank, (osc_s, _offset)) in self.osc_s.iter_mut().enumerate() {
+            if !on[bank] {
+                continue;
+            }
+
+            let voices = osc_s.len();
+
+            for (i, osc) in osc_s.iter_mut().enumerate() {
+                if osc.playing.is_none() {
+                    continue;
                 }
+
+                let pan = voice_pan(self.pan[bank], spread[bank], i, voices);
+                osc.render(&self.tables, lfo, pan, left, right);
             }
         }
 
-        let sample = sample * (self.volume + lfo_sample * 0.0125);
-        ((sample + self.chorus.get_sample(sample) + self.reverb.get_sample(sample)) / 3.0).tanh()
-        // println!("synth sample => {sample}");
-        // sample * self.volume
+        for ((left, right), lfo) in left.iter_mut().zip(right.iter_mut()).zip(lfo.iter()) {
+            let volume = self.volume + lfo * 0.0125;
+            *left *= volume;
+            *right *= volume;
+        }
+
+        let (mut chorus_l, mut chorus_r) = ([0.0; MAX_BLOCK], [0.0; MAX_BLOCK]);
+        let (mut reverb_l, mut reverb_r) = ([0.0; MAX_BLOCK], [0.0; MAX_BLOCK]);
+        self.chorus
+            .process(left, right, &mut chorus_l[..len], &mut chorus_r[..len]);
+        self.reverb
+            .process(left, right, &mut reverb_l[..len], &mut reverb_r[..len]);
+
+        let mix = |out: &mut [f32], chorus: [f32; MAX_BLOCK], reverb: [f32; MAX_BLOCK]| {
+            for ((sample, chorus), reverb) in out.iter_mut().zip(chorus).zip(reverb) {
+                *sample = ((*sample + chorus + reverb) / 3.0).tanh();
+            }
+        };
+        mix(left, chorus_l, reverb_l);
+        mix(right, chorus_r, reverb_r);
     }
 
-    pub fn play(&mut self, midi_note: MidiNote, _velocity: u8) {
-        // let midi_note = if midi_note >= 12 {
-        //     midi_note - 12
-        // } else {
-        //     return;
-        // };
+    /// true if oscillator bank `bank` is playing, it isn't muted and no other bank is soloed
+    pub fn bank_on(&self, bank: usize) -> bool {
+        !self.muted[bank] && (self.solo[bank] || !self.solo.contains(&true))
+    }
 
-        for (osc_s, _offset) in self.osc_s.iter_mut() {
-            for osc in osc_s {
-                if osc.playing == Some(midi_note) {
-                    return;
+    fn banks_on(&self) -> [bool; 3] {
+        std::array::from_fn(|bank| self.bank_on(bank))
+    }
+
+    /// the first bank that's on. its voices are the ones the voice allocator looks at.
+    fn lead_bank(&self) -> Option<usize> {
+        (0..3).find(|bank| self.bank_on(*bank))
+    }
+
+    /// shifts an oscillator bank by whole octaves, (-3..=3)
+    pub fn set_octave(&mut self, bank: usize, octave: i8) {
+        self.octave[bank % 3] = octave.clamp(-3, 3);
+        self.retune(bank % 3);
+    }
+
+    /// tunes an oscillator bank by `cents`, (-100.0..=100.0)
+    pub fn set_fine_tune(&mut self, bank: usize, cents: f32) {
+        self.fine_tune[bank % 3] = cents.clamp(-100.0, 100.0);
+        self.retune(bank % 3);
+    }
+
+    /// passes a bank's octave and fine tune on to its oscillators
+    fn retune(&mut self, bank: usize) {
+        let tuning = 2.0_f32.powf(self.octave[bank] as f32 + self.fine_tune[bank] / 1_200.0);
+
+        for osc in self.osc_s[bank].0.iter_mut() {
+            osc.set_tuning(tuning);
+        }
+    }
+
+    /// the most unison copies each voice can play at the current polyphony without going over
+    /// `UNISON_BUDGET`
+    pub fn max_unison(&self) -> usize {
+        (UNISON_BUDGET / self.voices.polyphony()).clamp(1, MAX_UNISON)
+    }
+
+    /// sets how many detuned copies of each note a bank plays and how they're spread out. the
+    /// number of copies is limited by `max_unison`.
+    pub fn set_unison(&mut self, bank: usize, unison: Unison) {
+        let unison = Unison {
+            voices: unison.voices.clamp(1, self.max_unison()),
+            detune: unison.detune.clamp(0.0, 100.0),
+            width: unison.width.clamp(0.0, 1.0),
+        };
+        self.unison[bank % 3] = unison;
+
+        for osc in self.osc_s[bank % 3].0.iter_mut() {
+            osc.set_unison(unison);
+        }
+    }
+
+    /// switches an oscillator bank off or back on
+    pub fn set_muted(&mut self, bank: usize, muted: bool) {
+        self.muted[bank % 3] = muted;
+        self.silence_off_banks();
+    }
+
+    /// solos an oscillator bank, or takes it out of solo
+    pub fn set_solo(&mut self, bank: usize, solo: bool) {
+        self.solo[bank % 3] = solo;
+        self.silence_off_banks();
+    }
+
+    /// cuts off every voice of the banks that are off, so they don't hold on to notes while
+    /// they're skipped.
+    fn silence_off_banks(&mut self) {
+        let on = self.banks_on();
+
+        for (bank, (osc_s, _offset)) in self.osc_s.iter_mut().enumerate() {
+            if !on[bank] {
+                osc_s.iter_mut().for_each(|osc| osc.reset());
+            }
+        }
+    }
+
+    pub fn play(&mut self, midi_note: MidiNote, velocity: u8) {
+        let mut velocity = self.velocity_curve.apply(velocity);
+
+        if self.soft_pedal {
+            velocity *= SOFT_PEDAL_LEVEL;
+        }
+
+        // the key is down again, so it's no longer only held by a pedal
+        self.held[midi_note as usize % 128] = false;
+
+        if self.voice_mode != VoiceMode::Poly {
+            self.mono_keys.retain(|key| *key != midi_note);
+            self.mono_keys.push(midi_note);
+            self.mono_velocity = velocity;
+
+            let Some(lead) = self.lead_bank() else {
+                return;
+            };
+
+            if let Some(key) = self.note_priority.pick(&self.mono_keys) {
+                let voice = &self.osc_s[lead].0[0];
+
+                // a held key that's still the one to play doesn't change anything
+                if voice.playing != Some(key) || !voice.is_pressed() || key == midi_note {
+                    self.play_mono(key);
                 }
             }
+
+            return;
         }
 
-        for (osc_s, offset) in self.osc_s.iter_mut() {
-            for osc in osc_s {
-                if osc.playing.is_none() {
-                    let note = if *offset > 0 {
-                        midi_note + (*offset as u8)
-                    } else {
-                        // println!("offset {} -> {}", offset, (offset.abs() as u8));
-                        midi_note - (offset.abs() as u8)
-                    };
-                    osc.press(note);
-                    osc.playing = Some(midi_note);
-                    // println!("playing note on osc {i}");
+        // muted banks don't take voices, every other bank plays on the voice the lead bank picks
+        let Some(lead) = self.lead_bank() else {
+            return;
+        };
+        let on = self.banks_on();
+        let allocation = self.voices.allocate(midi_note, &self.osc_s[lead].0);
 
-                    break;
+        for (bank, (osc_s, offset)) in self.osc_s.iter_mut().enumerate() {
+            if !on[bank] {
+                continue;
+            }
+
+            let osc = &mut osc_s[allocation.voice()];
+            let note = midi_note.saturating_add_signed(*offset as i8);
+
+            if let Allocation::Steal(_) = allocation {
+                osc.steal(note, velocity);
+            } else {
+                osc.press(note, velocity);
+            }
+
+            osc.playing = Some(midi_note);
+        }
+    }
+
+    /// plays `key` on the first voice, sliding to it from the note that's playing
+    fn play_mono(&mut self, key: MidiNote) {
+        let velocity = self.mono_velocity;
+        let legato = self.voice_mode == VoiceMode::Legato;
+        let on = self.banks_on();
+
+        for (bank, (osc_s, offset)) in self.osc_s.iter_mut().enumerate() {
+            if !on[bank] {
+                continue;
+            }
+
+            let osc = &mut osc_s[0];
+            let note = key.saturating_add_signed(*offset as i8);
+
+            match osc.playing {
+                Some(from) => {
+                    if !(legato && osc.is_pressed()) {
+                        osc.retrigger(velocity);
+                    }
+
+                    let samples = self
+                        .glide_mode
+                        .samples(self.glide, from, key, self.sample_rate);
+                    osc.glide(note, samples);
                 }
+                None => osc.press(note, velocity),
             }
+
+            osc.playing = Some(key);
         }
     }
 
+    /// switches between poly, mono and legato mode, releasing every note
+    pub fn set_voice_mode(&mut self, mode: VoiceMode) {
+        if mode != self.voice_mode {
+            self.stop_all();
+            self.voice_mode = mode;
+        }
+    }
+
+    /// sets how many notes can play at once, (1..=MAX_POLYPHONY)
+    pub fn set_polyphony(&mut self, polyphony: usize) {
+        let polyphony = polyphony.clamp(1, MAX_POLYPHONY);
+
+        for (osc_s, _offset) in self.osc_s.iter_mut() {
+            // new voices copy the settings of the first one
+            let mut voice = osc_s[0];
+            voice.reset();
+            osc_s.resize(polyphony, voice);
+        }
+
+        self.voices.set_polyphony(polyphony);
+
+        // more voices can leave less room for unison
+        for bank in 0..3 {
+            self.set_unison(bank, self.unison[bank]);
+        }
+    }
+
+    /// lets go of a key. the note keeps playing if the sustain pedal is down, or if it was held by
+    /// the sostenuto pedal.
     pub fn stop(&mut self, midi_note: MidiNote) {
+        let note = midi_note as usize % 128;
+
+        if self.sustain_pedal || (self.sostenuto_pedal && self.sostenuto_notes[note]) {
+            self.held[note] = true;
+        } else {
+            self.release_note(midi_note);
+        }
+    }
+
+    fn release_note(&mut self, midi_note: MidiNote) {
         // let midi_note = if midi_note >= 12 {
         //     midi_note - 12
         // } else {
         //     return;
         // };
 
+        if self.voice_mode != VoiceMode::Poly {
+            self.mono_keys.retain(|key| *key != midi_note);
+
+            let Some(lead) = self.lead_bank() else {
+                return;
+            };
+
+            if self.osc_s[lead].0[0].playing != Some(midi_note) {
+                return;
+            }
+
+            // go back to the keys that are still held
+            if let Some(key) = self.note_priority.pick(&self.mono_keys) {
+                self.play_mono(key);
+                return;
+            }
+        }
+
         for (osc_s, _offset) in self.osc_s.iter_mut() {
             for osc in osc_s {
                 // let note = if *offset > 0 {
@@ -321,6 +890,71 @@ impl Synth {
         }
     }
 
+    /// presses or lets go of the sustain pedal. letting go releases every note held by it.
+    pub fn set_sustain_pedal(&mut self, down: bool) {
+        self.sustain_pedal = down;
+
+        if !down {
+            self.release_held();
+        }
+    }
+
+    /// presses or lets go of the sostenuto pedal. pressing it holds only the notes that are down at
+    /// that moment.
+    pub fn set_sostenuto_pedal(&mut self, down: bool) {
+        if down && !self.sostenuto_pedal {
+            self.sostenuto_notes = [false; 128];
+
+            for (osc_s, _offset) in self.osc_s.iter() {
+                for osc in osc_s {
+                    if let Some(note) = osc.playing {
+                        let note = note as usize % 128;
+                        self.sostenuto_notes[note] |= osc.is_pressed() && !self.held[note];
+                    }
+                }
+            }
+        } else if !down {
+            self.sostenuto_notes = [false; 128];
+        }
+
+        self.sostenuto_pedal = down;
+
+        if !down {
+            self.release_held();
+        }
+    }
+
+    pub fn set_soft_pedal(&mut self, down: bool) {
+        self.soft_pedal = down;
+    }
+
+    /// releases the held notes that no pedal is holding anymore
+    fn release_held(&mut self) {
+        for note in 0..128 {
+            let by_sostenuto = self.sostenuto_pedal && self.sostenuto_notes[note];
+
+            if self.held[note] && !self.sustain_pedal && !by_sostenuto {
+                self.held[note] = false;
+                self.release_note(note as u8);
+            }
+        }
+    }
+
+    /// releases every note that is playing
+    pub fn stop_all(&mut self) {
+        self.mono_keys.clear();
+        self.held = [false; 128];
+        self.sostenuto_notes = [false; 128];
+
+        for (osc_s, _offset) in self.osc_s.iter_mut() {
+            for osc in osc_s {
+                if osc.playing.is_some() {
+                    osc.release();
+                }
+            }
+        }
+    }
+
     pub fn bend_all(&mut self, bend: f32) {
         for (osc_s, _offset) in self.osc_s.iter_mut() {
             for osc in osc_s {
@@ -377,6 +1011,60 @@ impl Synth {
         }
     }
 
+    /// runs `edit` on one of the envelopes of every voice
+    pub fn edit_envelope(&mut self, envelope: Envelope, edit: impl Fn(&mut ADSR)) {
+        for (osc_s, _offset) in self.osc_s.iter_mut() {
+            for osc in osc_s {
+                edit(osc.envelope_mut(envelope));
+            }
+        }
+    }
+
+    /// sets the shape of one of an envelope's stages
+    pub fn set_env_curve(&mut self, envelope: Envelope, stage: Stage, curve: Curve) {
+        self.edit_envelope(envelope, |env| env.set_curve(stage, curve));
+    }
+
+    /// sets how far the filter envelope sweeps the cutoff, (-1.0..=1.0)
+    pub fn set_filter_env_amount(&mut self, amount: f32) {
+        for (osc_s, _offset) in self.osc_s.iter_mut() {
+            for osc in osc_s {
+                osc.filter_env_amount = amount.clamp(-1.0, 1.0);
+            }
+        }
+    }
+
+    /// sets what the modulation envelope moves
+    pub fn set_mod_target(&mut self, target: ModTarget) {
+        for (osc_s, _offset) in self.osc_s.iter_mut() {
+            for osc in osc_s {
+                osc.mod_target = target;
+            }
+        }
+    }
+
+    /// sets how far the modulation envelope moves its target, (-1.0..=1.0)
+    pub fn set_mod_amount(&mut self, amount: f32) {
+        for (osc_s, _offset) in self.osc_s.iter_mut() {
+            for osc in osc_s {
+                osc.mod_amount = amount.clamp(-1.0, 1.0);
+            }
+        }
+    }
+
+    /// sets every setting of one of the envelopes from a patch
+    fn apply_envelope(&mut self, envelope: Envelope, patch: &EnvelopePatch) {
+        self.edit_envelope(envelope, |env| {
+            env.set_atk(patch.attack);
+            env.set_decay(patch.decay);
+            env.set_sus(patch.sustain);
+            env.set_release(patch.release);
+            env.set_curve(Stage::Attack, patch.attack_curve);
+            env.set_curve(Stage::Decay, patch.decay_curve);
+            env.set_curve(Stage::Release, patch.release_curve);
+        });
+    }
+
     pub fn set_cutoff(&mut self, cutoff: f32) {
         let cutoff = cutoff * 10_000.0;
 
@@ -395,6 +1083,15 @@ impl Synth {
         }
     }
 
+    /// how much soft notes close the low pass filter, (0.0..=1.0)
+    pub fn set_velocity_cutoff(&mut self, amount: f32) {
+        for (osc_s, _offset) in self.osc_s.iter_mut() {
+            for osc in osc_s {
+                osc.velocity_cutoff = amount;
+            }
+        }
+    }
+
     pub fn set_chorus_speed(&mut self, speed: f32) {
         self.chorus.set_speed(speed)
     }
@@ -408,5 +1105,208 @@ impl Synth {
         self.lfo.set_volume(speed);
     }
 
+    /// sets a parameter from a value in 0.0..=1.0
+    pub fn set_param(&mut self, param: Param, value: f32) {
+        match param {
+            Param::Volume => self.set_volume(value),
+            Param::Attack => self.set_atk(value),
+            Param::Decay => self.set_decay(value),
+            Param::Sustain => self.set_sus(value),
+            Param::Release => self.set_release(value),
+            Param::Cutoff => self.set_cutoff(value),
+            Param::Resonance => self.set_resonace(value),
+            Param::ChorusDepth => self.set_chorus_depth(value),
+            Param::ChorusSpeed => self.set_chorus_speed(value),
+            Param::LeslieSpeed => self.set_leslie_speed(value),
+            Param::ReverbGain => self.reverb.set_gain(value),
+            Param::ReverbDecay => self.reverb.set_decay(value),
+            Param::VelocityCutoff => self.set_velocity_cutoff(value),
+            Param::Glide => self.glide = value,
+            Param::TablePosition(osc) => self.table_position[osc % 3].position = value,
+            Param::OscVolume(osc) => self.osc_type[osc % 3].1 = value,
+            Param::OvertoneVolume(overtone) => {
+                self.overtones[overtone % 10].volume = value as f64;
+                self.set_overtones();
+            }
+            Param::FineTune(osc) => self.set_fine_tune(osc, value * 200.0 - 100.0),
+            Param::UnisonDetune(osc) => self.set_unison(
+                osc,
+                Unison {
+                    detune: value * 100.0,
+                    ..self.unison[osc % 3]
+                },
+            ),
+            Param::UnisonWidth(osc) => self.set_unison(
+                osc,
+                Unison {
+                    width: value,
+                    ..self.unison[osc % 3]
+                },
+            ),
+            Param::OscPan(osc) => self.pan[osc % 3] = value * 2.0 - 1.0,
+            Param::OscSpread(osc) => self.spread[osc % 3] = value,
+            Param::ChorusWidth => self.chorus.set_width(value),
+            Param::FilterAttack => self.edit_envelope(Envelope::Filter, |env| env.set_atk(value)),
+            Param::FilterDecay => self.edit_envelope(Envelope::Filter, |env| env.set_decay(value)),
+            Param::FilterSustain => self.edit_envelope(Envelope::Filter, |env| env.set_sus(value)),
+            Param::FilterRelease => {
+                self.edit_envelope(Envelope::Filter, |env| env.set_release(value))
+            }
+            Param::FilterEnvAmount => self.set_filter_env_amount(value * 2.0 - 1.0),
+            Param::ModAttack => self.edit_envelope(Envelope::Mod, |env| env.set_atk(value)),
+            Param::ModDecay => self.edit_envelope(Envelope::Mod, |env| env.set_decay(value)),
+            Param::ModSustain => self.edit_envelope(Envelope::Mod, |env| env.set_sus(value)),
+            Param::ModRelease => self.edit_envelope(Envelope::Mod, |env| env.set_release(value)),
+            Param::ModEnvAmount => self.set_mod_amount(value * 2.0 - 1.0),
+        }
+    }
+
+    /// sets every parameter of the synth from a patch
+    pub fn apply_patch(&mut self, patch: &Patch) {
+        self.set_volume(patch.volume);
+
+        for (i, osc) in patch.oscillators.iter().enumerate() {
+            self.osc_type[i].1 = osc.volume;
+            self.table_position[i] = osc.position;
+            self.osc_s[i].1 = osc.detune.clamp(-12, 12);
+            self.set_octave(i, osc.octave);
+            self.set_fine_tune(i, osc.fine_tune);
+            self.pan[i] = osc.pan.clamp(-1.0, 1.0);
+            self.spread[i] = osc.spread.clamp(0.0, 1.0);
+            self.muted[i] = osc.muted;
+            self.solo[i] = osc.solo;
+
+            // the rest of the patch still loads without the table
+            if let Err(e) = self.set_wave(i, &osc.wave) {
+                println!("[ERROR] => osc {}: {e}", i + 1);
+            }
+        }
+
+        self.silence_off_banks();
+
+        self.overtones = patch.overtones;
+        self.set_overtones();
+
+        self.apply_envelope(Envelope::Amp, &patch.envelope);
+
+        // patches saved before the filter had its own envelope sweep it with the amp envelope
+        let filter_env = patch.filter_envelope.unwrap_or(FilterEnvelopePatch {
+            envelope: patch.envelope,
+            amount: 1.0,
+        });
+        self.apply_envelope(Envelope::Filter, &filter_env.envelope);
+        self.set_filter_env_amount(filter_env.amount);
+
+        let mod_env = patch.mod_envelope.unwrap_or(ModEnvelopePatch {
+            envelope: patch.envelope,
+            target: ModTarget::default(),
+            amount: 0.0,
+        });
+        self.apply_envelope(Envelope::Mod, &mod_env.envelope);
+        self.set_mod_target(mod_env.target);
+        self.set_mod_amount(mod_env.amount);
+
+        self.set_cutoff(patch.filter.cutoff);
+        self.set_resonace(patch.filter.resonance);
+
+        self.lfo.set_frequency(patch.lfo.frequency);
+        self.lfo.set_volume(patch.lfo.volume);
+
+        self.chorus.turn_power_on(patch.chorus.power);
+        self.chorus.set_volume(patch.chorus.volume);
+        self.chorus.set_speed(patch.chorus.speed);
+        self.chorus.set_width(patch.chorus.width);
+
+        self.reverb.turn_power_on(patch.reverb.power);
+        self.reverb.set_gain(patch.reverb.gain);
+        self.reverb.set_decay(patch.reverb.decay);
+
+        self.set_polyphony(patch.voices.polyphony);
+
+        for (i, osc) in patch.oscillators.iter().enumerate() {
+            self.set_unison(i, osc.unison);
+        }
+
+        self.voices.policy = patch.voices.stealing;
+        self.set_voice_mode(patch.voices.mode);
+        self.note_priority = patch.voices.priority;
+        self.glide = patch.voices.glide;
+        self.glide_mode = patch.voices.glide_mode;
+
+        self.velocity_curve = patch.velocity.curve;
+        self.set_velocity_cutoff(patch.velocity.cutoff);
+    }
+
+    /// captures the current value of every parameter of the synth
+    pub fn snapshot_patch(&self) -> Patch {
+        // all oscillators share the same envelope and filter settings.
+        let osc = &self.osc_s[0].0[0];
+
+        Patch {
+            volume: self.volume,
+            oscillators: [0, 1, 2].map(|i| OscPatch {
+                wave: self.wave_tables.load().wave(self.osc_type[i].0),
+                volume: self.osc_type[i].1,
+                detune: self.osc_s[i].1,
+                octave: self.octave[i],
+                fine_tune: self.fine_tune[i],
+                unison: self.unison[i],
+                position: self.table_position[i],
+                pan: self.pan[i],
+                spread: self.spread[i],
+                muted: self.muted[i],
+                solo: self.solo[i],
+            }),
+            overtones: self.overtones,
+            envelope: EnvelopePatch::from(&osc.env_filter),
+            filter_envelope: Some(FilterEnvelopePatch {
+                envelope: EnvelopePatch::from(&osc.filter_env),
+                amount: osc.filter_env_amount,
+            }),
+            mod_envelope: Some(ModEnvelopePatch {
+                envelope: EnvelopePatch::from(&osc.mod_env),
+                target: osc.mod_target,
+                amount: osc.mod_amount,
+            }),
+            filter: FilterPatch {
+                cutoff: osc.low_pass.cutoff() / 10_000.0,
+                resonance: osc.low_pass.resonance(),
+            },
+            lfo: LfoPatch {
+                frequency: self.lfo.frequency(),
+                volume: self.lfo.volume(),
+            },
+            chorus: ChorusPatch {
+                power: self.chorus.power,
+                volume: self.chorus.volume,
+                speed: self.chorus.speed,
+                width: self.chorus.width,
+            },
+            reverb: ReverbPatch {
+                power: self.reverb.power,
+                gain: self.reverb.gain,
+                decay: self.reverb.decay,
+            },
+            voices: VoicePatch {
+                polyphony: self.voices.polyphony(),
+                stealing: self.voices.policy,
+                mode: self.voice_mode,
+                priority: self.note_priority,
+                glide: self.glide,
+                glide_mode: self.glide_mode,
+            },
+            velocity: VelocityPatch {
+                curve: self.velocity_curve,
+                cutoff: osc.velocity_cutoff,
+            },
+        }
+    }
+
     // pub fn set_atk(&mut self, atk: f32) {}
 }
+
+impl Default for Synth {
+    fn default() -> Self {
+        Self::new(DEFAULT_SAMPLE_RATE)
+    }
+}
@@ -0,0 +1,148 @@
+use crate::{osc::Overtone, synth::WaveTables, wav_table::WavTable};
+use arc_swap::ArcSwap;
+use rtrb::{Consumer, Producer, RingBuffer};
+use std::{
+    sync::{Arc, Condvar, Mutex},
+    thread::{self, spawn, Thread},
+    time::Duration,
+};
+
+/// how often tables that were swapped out are checked to see if they can be freed
+const RETIRE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// how many rebuilds can be waiting for the builder thread at once
+const REQUEST_QUEUE_SIZE: usize = 16;
+
+type Request = (u64, [Overtone; 10]);
+
+/// builds wave tables on a background thread and publishes them with an atomic pointer swap, so
+/// the audio thread never waits on table generation.
+#[derive(Debug)]
+pub struct TableBuilder {
+    /// the tables being played, swapped out when a rebuild finishes
+    pub tables: Arc<ArcSwap<WaveTables>>,
+    /// requests are queued without locking or allocating, so they can be made from the audio
+    /// thread
+    requests: Producer<Request>,
+    /// the builder thread, unparked when a request is queued
+    thread: Thread,
+    /// the newest request, if it didn't fit in the queue
+    pending: Option<Request>,
+    /// the number of the last request made
+    sent: u64,
+    /// the number of the last request published, and a way to wait on it
+    built: Arc<(Mutex<u64>, Condvar)>,
+    /// tables that were swapped out, kept until nothing else holds them so they're freed here
+    /// instead of on the audio thread
+    retired: Arc<Mutex<Vec<Arc<WaveTables>>>>,
+}
+
+impl TableBuilder {
+    /// starts the builder thread. it stops when the builder is dropped.
+    pub fn new(tables: WaveTables) -> Self {
+        let tables = Arc::new(ArcSwap::from_pointee(tables));
+        let built = Arc::new((Mutex::new(0), Condvar::new()));
+        let retired = Arc::new(Mutex::new(Vec::new()));
+        let (requests, rx) = RingBuffer::new(REQUEST_QUEUE_SIZE);
+
+        let (t, b, r) = (tables.clone(), built.clone(), retired.clone());
+        let thread = spawn(move || Self::run(t, b, r, rx)).thread().clone();
+
+        Self {
+            tables,
+            requests,
+            thread,
+            pending: None,
+            sent: 0,
+            built,
+            retired,
+        }
+    }
+
+    fn run(
+        tables: Arc<ArcSwap<WaveTables>>,
+        built: Arc<(Mutex<u64>, Condvar)>,
+        retired: Arc<Mutex<Vec<Arc<WaveTables>>>>,
+        mut rx: Consumer<Request>,
+    ) {
+        loop {
+            // skip to the newest request, older ones would be replaced right away
+            let Some((n, overtones)) = std::iter::from_fn(|| rx.pop().ok()).last() else {
+                if rx.is_abandoned() {
+                    return;
+                }
+
+                retired
+                    .lock()
+                    .unwrap()
+                    .retain(|tables| Arc::strong_count(tables) > 1);
+                thread::park_timeout(RETIRE_INTERVAL);
+                continue;
+            };
+
+            let new_tables = WaveTables::new(&overtones);
+
+            let old = tables.rcu(|current| WaveTables {
+                custom: current.custom.clone(),
+                ..new_tables.clone()
+            });
+            retired.lock().unwrap().push(old);
+
+            let (lock, cvar) = &*built;
+            *lock.lock().unwrap() = n;
+            cvar.notify_all();
+        }
+    }
+
+    /// asks for the tables to be rebuilt from `overtones`. returns right away, the new tables are
+    /// swapped in when they're ready. returns the request number to pass to `wait`. never locks
+    /// or allocates.
+    pub fn rebuild(&mut self, overtones: [Overtone; 10]) -> u64 {
+        self.sent += 1;
+        self.pending = Some((self.sent, overtones));
+        self.send_pending();
+
+        self.sent
+    }
+
+    /// queues the request that didn't fit last time, if there is one. called every block so it
+    /// isn't held back until the next rebuild.
+    pub fn send_pending(&mut self) {
+        let Some(request) = self.pending else {
+            return;
+        };
+
+        if self.requests.push(request).is_ok() {
+            self.pending = None;
+            self.thread.unpark();
+        }
+    }
+
+    /// blocks until the tables from request `n` (or a newer one) are being played. used when
+    /// rendering offline, never call this from the audio thread.
+    pub fn wait(&self, n: u64) {
+        let (lock, cvar) = &*self.built;
+        let _built = cvar
+            .wait_while(lock.lock().unwrap(), |built| *built < n)
+            .unwrap();
+    }
+
+    /// blocks until the last requested rebuild is being played
+    pub fn flush(&mut self) {
+        while self.pending.is_some() {
+            thread::yield_now();
+            self.send_pending();
+        }
+
+        self.wait(self.sent);
+    }
+
+    /// swaps in a new set of custom tables, keeping the built in ones
+    pub fn set_custom(&self, custom: Arc<[WavTable]>) {
+        let old = self.tables.rcu(|current| WaveTables {
+            custom: custom.clone(),
+            ..(**current).clone()
+        });
+        self.retired.lock().unwrap().push(old);
+    }
+}
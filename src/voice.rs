@@ -0,0 +1,228 @@
+use crate::osc::Oscillator;
+use serde::{Deserialize, Serialize};
+use std::{cmp::Ordering, fmt::Display};
+
+/// how many voices the synth plays at once by default
+pub const DEFAULT_POLYPHONY: usize = 10;
+/// the most voices the synth can be set to play at once
+pub const MAX_POLYPHONY: usize = 32;
+
+/// which voice is taken when every voice is busy. voices that are releasing are always taken
+/// first, (quietest first) before any held note is cut off.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum StealPolicy {
+    /// the voice that was started first
+    #[default]
+    Oldest,
+    /// the voice with the lowest envelope level
+    Quietest,
+    /// the lowest note, keeps the melody on top
+    LowestPriority,
+}
+
+impl StealPolicy {
+    pub const ALL: [Self; 3] = [Self::Oldest, Self::Quietest, Self::LowestPriority];
+}
+
+impl Display for StealPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Oldest => write!(f, "Oldest"),
+            Self::Quietest => write!(f, "Quietest"),
+            Self::LowestPriority => write!(f, "Lowest"),
+        }
+    }
+}
+
+/// how many notes the synth plays at once
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum VoiceMode {
+    #[default]
+    Poly,
+    /// one note at a time, every new note restarts the envelope
+    Mono,
+    /// one note at a time, overlapping notes slide without restarting the envelope
+    Legato,
+}
+
+impl VoiceMode {
+    pub const ALL: [Self; 3] = [Self::Poly, Self::Mono, Self::Legato];
+}
+
+impl Display for VoiceMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Poly => write!(f, "Poly"),
+            Self::Mono => write!(f, "Mono"),
+            Self::Legato => write!(f, "Legato"),
+        }
+    }
+}
+
+/// which of the held keys is played in mono and legato mode
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum NotePriority {
+    /// the key pressed last
+    #[default]
+    Last,
+    /// the lowest key
+    Low,
+    /// the highest key
+    High,
+}
+
+impl NotePriority {
+    pub const ALL: [Self; 3] = [Self::Last, Self::Low, Self::High];
+
+    /// picks the key to play from the held keys, (in the order they were pressed)
+    pub fn pick(&self, keys: &[u8]) -> Option<u8> {
+        match self {
+            Self::Last => keys.last().copied(),
+            Self::Low => keys.iter().min().copied(),
+            Self::High => keys.iter().max().copied(),
+        }
+    }
+}
+
+impl Display for NotePriority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Last => write!(f, "Last"),
+            Self::Low => write!(f, "Low"),
+            Self::High => write!(f, "High"),
+        }
+    }
+}
+
+/// how the glide time is used
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum GlideMode {
+    /// every glide takes the glide time
+    #[default]
+    ConstantTime,
+    /// the glide time is the time taken to slide an octave
+    ConstantRate,
+}
+
+impl GlideMode {
+    pub const ALL: [Self; 2] = [Self::ConstantTime, Self::ConstantRate];
+
+    /// how many samples a glide from `from` to `to` takes
+    pub fn samples(&self, glide: f32, from: u8, to: u8, sample_rate: u32) -> f32 {
+        let samples = glide * sample_rate as f32;
+
+        match self {
+            Self::ConstantTime => samples,
+            Self::ConstantRate => samples * from.abs_diff(to) as f32 / 12.0,
+        }
+    }
+}
+
+impl Display for GlideMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ConstantTime => write!(f, "Const. Time"),
+            Self::ConstantRate => write!(f, "Const. Rate"),
+        }
+    }
+}
+
+/// how a note got its voice
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Allocation {
+    /// the voice wasn't playing anything
+    Free(usize),
+    /// the voice was already playing the same note, (held or releasing)
+    Retrigger(usize),
+    /// the voice was cut off to make room, it should be faded out before the new note starts.
+    Steal(usize),
+}
+
+impl Allocation {
+    pub fn voice(&self) -> usize {
+        match self {
+            Self::Free(voice) | Self::Retrigger(voice) | Self::Steal(voice) => *voice,
+        }
+    }
+}
+
+/// picks which voice plays each new note
+#[derive(Clone, Debug)]
+pub struct VoiceAllocator {
+    pub policy: StealPolicy,
+    /// when each voice was last given a note
+    started: Vec<u64>,
+    /// counts up every time a note is allocated
+    clock: u64,
+}
+
+impl VoiceAllocator {
+    pub fn new(polyphony: usize) -> Self {
+        // `set_polyphony` can be called on the audio thread, it only ever resizes within this
+        let mut started = Vec::with_capacity(MAX_POLYPHONY);
+        started.resize(polyphony, 0);
+
+        Self {
+            policy: StealPolicy::default(),
+            started,
+            clock: 0,
+        }
+    }
+
+    pub fn polyphony(&self) -> usize {
+        self.started.len()
+    }
+
+    pub fn set_polyphony(&mut self, polyphony: usize) {
+        self.started.resize(polyphony, 0);
+    }
+
+    /// picks a voice for `midi_note`. `voices` are the oscillators of one of the synth's
+    /// oscillator banks, all banks play the same note on the same voice.
+    pub fn allocate(&mut self, midi_note: u8, voices: &[Oscillator]) -> Allocation {
+        let voices = &voices[..self.polyphony().min(voices.len())];
+        self.clock += 1;
+
+        let allocation =
+            if let Some(voice) = voices.iter().position(|osc| osc.playing == Some(midi_note)) {
+                Allocation::Retrigger(voice)
+            } else if let Some(voice) = voices.iter().position(|osc| osc.playing.is_none()) {
+                Allocation::Free(voice)
+            } else if let Some(voice) =
+                Self::min_by(voices, |_, osc| (!osc.is_pressed()).then_some(osc.level()))
+            {
+                Allocation::Steal(voice)
+            } else {
+                let voice = match self.policy {
+                    StealPolicy::Oldest => Self::min_by(voices, |i, _| Some(self.started[i])),
+                    StealPolicy::Quietest => Self::min_by(voices, |_, osc| Some(osc.level())),
+                    StealPolicy::LowestPriority => Self::min_by(voices, |_, osc| osc.playing),
+                };
+
+                Allocation::Steal(voice.unwrap_or(0))
+            };
+
+        self.started[allocation.voice()] = self.clock;
+
+        allocation
+    }
+
+    /// the index of the voice with the lowest key, skipping voices with no key.
+    fn min_by<K: PartialOrd>(
+        voices: &[Oscillator],
+        key: impl Fn(usize, &Oscillator) -> Option<K>,
+    ) -> Option<usize> {
+        voices
+            .iter()
+            .enumerate()
+            .filter_map(|(i, osc)| key(i, osc).map(|key| (i, key)))
+            .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+            .map(|(i, _)| i)
+    }
+}
+
+impl Default for VoiceAllocator {
+    fn default() -> Self {
+        Self::new(DEFAULT_POLYPHONY)
+    }
+}
@@ -0,0 +1,143 @@
+use crate::{
+    mipmap::MipMap,
+    synth::{WaveTable, WAVE_TABLE_SIZE},
+};
+use anyhow::{bail, Result};
+use hound::{SampleFormat, WavReader};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// the frame size used by most wave table synths, (Serum, Vital, etc) WAV files that are a
+/// multiple of this long are split into frames of this size.
+pub const FRAME_SIZE: usize = 2048;
+/// the most frames kept from a multi-frame WAV file
+pub const MAX_FRAMES: usize = 256;
+
+/// a wave table imported from a WAV file. holds one or more single cycle frames.
+#[derive(Clone, PartialEq, PartialOrd, Debug)]
+pub struct WavTable {
+    pub name: Arc<str>,
+    pub frames: Arc<[MipMap]>,
+}
+
+impl WavTable {
+    /// reads a single cycle or multi-frame WAV file. only the first channel is used.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let mut reader = WavReader::open(path)?;
+        let spec = reader.spec();
+        let channels = spec.channels as usize;
+
+        let samples: Vec<f32> = match spec.sample_format {
+            SampleFormat::Float => reader.samples::<f32>().collect::<Result<_, _>>()?,
+            SampleFormat::Int => {
+                let max = (1_i64 << (spec.bits_per_sample - 1)) as f32;
+
+                reader
+                    .samples::<i32>()
+                    .map(|sample| sample.map(|sample| sample as f32 / max))
+                    .collect::<Result<_, _>>()?
+            }
+        };
+        let samples: Vec<f32> = samples.into_iter().step_by(channels.max(1)).collect();
+
+        if samples.is_empty() {
+            bail!("{} has no samples", path.display());
+        }
+
+        let name = path
+            .file_stem()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        Ok(Self::from_samples(name, &samples))
+    }
+
+    /// splits `samples` into frames of `FRAME_SIZE` if it's a multiple of that long, otherwise
+    /// the whole thing is used as one cycle.
+    pub fn from_samples(name: impl Into<Arc<str>>, samples: &[f32]) -> Self {
+        let frames = if samples.len() > FRAME_SIZE && samples.len().is_multiple_of(FRAME_SIZE) {
+            samples
+                .chunks(FRAME_SIZE)
+                .take(MAX_FRAMES)
+                .map(|frame| MipMap::new(&resample(frame)))
+                .collect()
+        } else {
+            [MipMap::new(&resample(samples))].into()
+        };
+
+        Self {
+            name: name.into(),
+            frames,
+        }
+    }
+
+    /// where imported wave tables are kept. (`~/.config/synth-rt/wavetables/` on linux)
+    pub fn default_dir() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("synth-rt").join("wavetables"))
+    }
+
+    /// loads every WAV file in `dir`, sorted by file name. the index of a table in the list is
+    /// its `OscType::Custom` id, patches find their tables by name.
+    pub fn load_dir(dir: impl AsRef<Path>) -> Vec<Self> {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return Vec::new();
+        };
+
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("wav"))
+            })
+            .collect();
+        paths.sort();
+
+        paths
+            .into_iter()
+            .filter_map(|path| match Self::load(&path) {
+                Ok(table) => Some(table),
+                Err(e) => {
+                    println!(
+                        "[ERROR] => couldn't load wave table {}: {e}",
+                        path.display()
+                    );
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// loads the wave tables in `default_dir`
+    pub fn load_default() -> Vec<Self> {
+        Self::default_dir().map(Self::load_dir).unwrap_or_default()
+    }
+}
+
+/// resamples one cycle to `WAVE_TABLE_SIZE` samples. longer cycles are averaged down so the
+/// harmonics that don't fit in the table are filtered out instead of aliasing.
+pub fn resample(cycle: &[f32]) -> WaveTable {
+    let len = cycle.len();
+    let step = len as f32 / WAVE_TABLE_SIZE as f32;
+
+    (0..WAVE_TABLE_SIZE)
+        .map(|i| {
+            let start = i as f32 * step;
+
+            if step <= 1.0 {
+                let index = start as usize;
+                let frac = start - index as f32;
+
+                cycle[index % len] * (1.0 - frac) + cycle[(index + 1) % len] * frac
+            } else {
+                let end = start + step;
+                let samples = (start as usize..end.ceil() as usize).map(|j| cycle[j % len]);
+
+                samples.clone().sum::<f32>() / samples.count() as f32
+            }
+        })
+        .collect()
+}
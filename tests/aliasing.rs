@@ -0,0 +1,108 @@
+use std::{f64::consts::PI, sync::Arc};
+use synth_rt::{
+    mipmap::MipMap,
+    osc::{TableMix, TablePosition, WavetableOscillator},
+    synth::{WaveTable, WAVE_TABLE_SIZE},
+    DEFAULT_SAMPLE_RATE,
+};
+
+/// A7, a tenth of a second is a whole number of cycles so every harmonic and alias lands on a DFT
+/// bin.
+const FREQUENCY: f32 = 3_520.0;
+const LEN: usize = DEFAULT_SAMPLE_RATE as usize / 10;
+
+fn saw() -> WaveTable {
+    (0..WAVE_TABLE_SIZE)
+        .map(|i| 2.0 * i as f32 / WAVE_TABLE_SIZE as f32 - 1.0)
+        .collect()
+}
+
+/// reads a table the way the oscillator did before it was band limited
+fn naive(table: &[f32]) -> Vec<f32> {
+    let step = FREQUENCY * WAVE_TABLE_SIZE as f32 / DEFAULT_SAMPLE_RATE as f32;
+    let mut index = 0.0_f32;
+
+    (0..LEN)
+        .map(|_| {
+            let i = index as usize;
+            let frac = index - i as f32;
+            let sample = table[i] * (1.0 - frac) + table[(i + 1) % WAVE_TABLE_SIZE] * frac;
+            index = (index + step) % WAVE_TABLE_SIZE as f32;
+
+            sample
+        })
+        .collect()
+}
+
+fn band_limited(table: &[f32]) -> Vec<f32> {
+    let tables = [TableMix {
+        frames: Arc::new([MipMap::new(table)]),
+        volume: 1.0,
+        position: TablePosition::default(),
+    }];
+    let mut osc = WavetableOscillator::new(DEFAULT_SAMPLE_RATE);
+    osc.set_frequency(FREQUENCY);
+
+    (0..LEN)
+        .map(|_| osc.get_sample(&tables, 0.0, 1.0, 0.0))
+        .collect()
+}
+
+/// how much of the signal's energy isn't at a harmonic of `FREQUENCY`, in dB
+fn aliased_energy(signal: &[f32]) -> f64 {
+    let n = signal.len();
+    let bin_width = DEFAULT_SAMPLE_RATE as f64 / n as f64;
+    let (mut total, mut aliased) = (0.0, 0.0);
+
+    for bin in 1..n / 2 {
+        let (re, im) = signal
+            .iter()
+            .enumerate()
+            .fold((0.0, 0.0), |(re, im), (i, sample)| {
+                let phase = 2.0 * PI * ((bin * i) % n) as f64 / n as f64;
+                (
+                    re + *sample as f64 * phase.cos(),
+                    im - *sample as f64 * phase.sin(),
+                )
+            });
+        let power = re * re + im * im;
+        let harmonic = (bin as f64 * bin_width / FREQUENCY as f64).fract() == 0.0;
+
+        total += power;
+
+        if !harmonic {
+            aliased += power;
+        }
+    }
+
+    10.0 * (aliased / total).log10()
+}
+
+#[test]
+fn high_saw_is_band_limited() {
+    let naive = aliased_energy(&naive(&saw()));
+    let band_limited = aliased_energy(&band_limited(&saw()));
+    println!("aliased energy => naive: {naive:.1} dB, band limited: {band_limited:.1} dB");
+
+    assert!(
+        naive > -30.0,
+        "the test signal should alias when not band limited"
+    );
+    assert!(band_limited < -50.0, "{band_limited:.1} dB of aliasing");
+}
+
+#[test]
+fn low_notes_keep_every_harmonic() {
+    assert_eq!(MipMap::pick_level(0.5), (0, 0.0));
+
+    let mipmap = MipMap::new(&saw());
+    let max_error = saw()
+        .iter()
+        .zip(mipmap.table().iter())
+        .skip(1)
+        .map(|(a, b)| (a - b).abs())
+        .fold(0.0, f32::max);
+
+    // only the gibbs ripple near the jump differs
+    assert!(max_error < 0.2, "{max_error}");
+}
@@ -0,0 +1,180 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+use synth_rt::{
+    event::{self, Event},
+    osc::TablePosition,
+    synth::{OscType, Param, Synth},
+    voice::VoiceMode,
+    wav_table::WavTable,
+    Player, BLOCK_SIZE, DEFAULT_SAMPLE_RATE,
+};
+
+/// counts the allocations made by the thread it's turned on for
+struct CountingAllocator;
+
+thread_local! {
+    static COUNTING: Cell<bool> = const { Cell::new(false) };
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if COUNTING.with(Cell::get) {
+            ALLOCATIONS.with(|n| n.set(n.get() + 1));
+        }
+
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if COUNTING.with(Cell::get) {
+            ALLOCATIONS.with(|n| n.set(n.get() + 1));
+        }
+
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if COUNTING.with(Cell::get) {
+            ALLOCATIONS.with(|n| n.set(n.get() + 1));
+        }
+
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// how many times `run` allocated or freed memory
+fn allocations(run: impl FnOnce()) -> usize {
+    ALLOCATIONS.with(|n| n.set(0));
+    COUNTING.with(|counting| counting.set(true));
+    run();
+    COUNTING.with(|counting| counting.set(false));
+
+    ALLOCATIONS.with(Cell::get)
+}
+
+#[test]
+fn applying_edits_never_allocates() {
+    let mut synth = Synth::default();
+    synth.set_wav_tables(vec![WavTable::from_samples("ramp", &[0.0, 0.5, 1.0, -0.5])]);
+    let (handle, mut events) = event::queue(&synth);
+
+    let mut patch = Synth::default().snapshot_patch();
+    patch.volume = 0.5;
+    patch.overtones[2].overtone = 3.0;
+    patch.voices.polyphony = 16;
+
+    // the boxes are made here, and freed here once the audio thread sends them back
+    handle.send(Event::Param(Param::Cutoff, 0.25));
+    handle.send(Event::Patch(Box::new(patch)));
+    handle.send(Event::NoteOn {
+        note: 60,
+        velocity: 100,
+    });
+    handle.edit(|synth| {
+        synth.set_polyphony(4);
+        synth.set_voice_mode(VoiceMode::Legato);
+        synth.osc_type[0].0 = OscType::Custom(0);
+        synth.overtones[4].overtone = 5.0;
+        synth.set_overtones();
+    });
+    handle.send(Event::Param(Param::Resonance, 0.5));
+
+    let n = allocations(|| events.apply(&mut synth));
+    assert_eq!(n, 0, "{n} allocations applying edits");
+
+    // the edits were published for the UI
+    let state = handle.state();
+    assert_eq!(state.volume, 0.5);
+    assert_eq!(state.voices.polyphony, 4);
+    assert_eq!(state.voices.mode, VoiceMode::Legato);
+    assert_eq!(state.overtones[4].overtone, 5.0);
+    assert_eq!(state.filter.resonance, 0.5);
+    assert_eq!(
+        synth
+            .wave_tables
+            .load()
+            .osc_type(&state.oscillators[0].wave)
+            .unwrap(),
+        OscType::Custom(0)
+    );
+}
+
+/// plays `samples` samples through the player
+fn play(player: &mut Player, samples: usize) {
+    for _ in 0..samples {
+        player.next();
+    }
+}
+
+#[test]
+fn rendering_never_allocates() {
+    let mut synth = Synth::default();
+    synth.chorus.turn_power_on(true);
+    synth.reverb.turn_power_on(true);
+    let (mut player, handle) = Player::new(synth);
+
+    // the first block sets up anything that's allocated once per thread
+    play(&mut player, BLOCK_SIZE);
+
+    let n = allocations(|| {
+        for note in [48, 52, 55, 60, 64, 67, 72] {
+            handle.send(Event::NoteOn {
+                note,
+                velocity: 100,
+            });
+        }
+
+        handle.send(Event::SustainPedal(true));
+        handle.send(Event::PitchBend(0.5));
+        play(&mut player, DEFAULT_SAMPLE_RATE as usize / 2);
+
+        for note in [48, 52, 55] {
+            handle.send(Event::NoteOff(note));
+        }
+
+        handle.send(Event::SustainPedal(false));
+        handle.send(Event::PitchBend(0.0));
+        // more notes than voices, so some are stolen
+        for note in 80..100 {
+            handle.send(Event::NoteOn {
+                note,
+                velocity: 100,
+            });
+        }
+
+        play(&mut player, DEFAULT_SAMPLE_RATE as usize / 2);
+    });
+
+    assert_eq!(n, 0, "{n} allocations while rendering");
+}
+
+#[test]
+fn changing_tables_never_allocates() {
+    let mut synth = Synth::default();
+    synth.play(60, 100);
+    synth.render(&mut [0.0; BLOCK_SIZE]);
+
+    let n = allocations(|| {
+        synth.osc_type[1].0 = OscType::Sqr;
+        synth.table_position[2] = TablePosition {
+            position: 0.5,
+            ..Default::default()
+        };
+        synth.render(&mut [0.0; BLOCK_SIZE]);
+    });
+    assert_eq!(n, 0, "{n} allocations after changing the wave forms");
+
+    // the new tables are built on another thread, swapping them in doesn't allocate or free
+    synth.overtones[3].overtone = 2.0;
+    synth.set_overtones();
+    synth.wait_for_tables();
+
+    let n = allocations(|| synth.render(&mut [0.0; BLOCK_SIZE]));
+    assert_eq!(n, 0, "{n} allocations swapping in new wave tables");
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use common::temp_dir;
+use std::fs;
+use synth_rt::{
+    bank::{Bank, BANK_SIZE},
+    patch::Patch,
+    synth::Synth,
+};
+
+fn quiet() -> Patch {
+    let mut patch = Synth::default().snapshot_patch();
+    patch.volume = 0.25;
+
+    patch
+}
+
+#[test]
+fn prev_and_next_wrap_around() {
+    let mut bank = Bank::load(temp_dir("bank-wrap"));
+    assert_eq!(bank.current, 0);
+
+    bank.select_prev();
+    assert_eq!(bank.current, 127);
+
+    bank.select_next();
+    assert_eq!(bank.current, 0);
+
+    bank.select_next();
+    assert_eq!(bank.current, 1);
+
+    // program numbers past the end wrap too
+    bank.select(BANK_SIZE + 5);
+    assert_eq!(bank.current, 5);
+}
+
+#[test]
+fn empty_slots_have_no_preset() {
+    let mut bank = Bank::load(temp_dir("bank-empty"));
+
+    assert!(bank.current().is_none());
+    assert!(bank.select(64).is_none());
+    assert!(bank.select_next().is_none());
+    assert!(bank.program_change(3).is_none());
+    assert_eq!(bank.current, 3);
+
+    // an empty slot can't be renamed
+    assert!(bank.rename("nothing").is_err());
+}
+
+#[test]
+fn presets_are_reloaded_from_disk() {
+    let dir = temp_dir("bank-reload");
+    let mut bank = Bank::load(&dir);
+    bank.select(127);
+    bank.save_as("quiet", quiet()).unwrap();
+    assert!(dir.join("127.toml").exists());
+
+    let mut reloaded = Bank::load(&dir);
+    let preset = reloaded.select(127).unwrap();
+    assert_eq!(preset.name, "quiet");
+    assert_eq!(preset.patch, quiet());
+    assert!(reloaded.get(126).is_none());
+
+    assert_eq!(reloaded.program_change(127), Some(quiet()));
+}
+
+#[test]
+fn rename_keeps_the_patch() {
+    let dir = temp_dir("bank-rename");
+    let mut bank = Bank::load(&dir);
+    bank.select(10);
+    bank.save_as("quiet", quiet()).unwrap();
+    bank.rename("softer").unwrap();
+
+    let preset = bank.current().unwrap();
+    assert_eq!(preset.name, "softer");
+    assert_eq!(preset.patch, quiet());
+
+    let preset = Bank::load(&dir).get(10).cloned().unwrap();
+    assert_eq!(preset.name, "softer");
+}
+
+#[test]
+fn broken_preset_files_leave_the_slot_empty() {
+    let dir = temp_dir("bank-broken");
+    fs::write(dir.join("002.toml"), "not a preset").unwrap();
+
+    assert!(Bank::load(&dir).get(2).is_none());
+}
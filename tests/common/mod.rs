@@ -0,0 +1,19 @@
+//! helpers shared by the integration tests, not every test file uses all of them
+#![allow(dead_code)]
+
+use std::{env, fs, path::PathBuf};
+use synth_rt::synth::Synth;
+
+/// an empty directory for a test to write its files to
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join("synth-rt-test").join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    dir
+}
+
+/// true if any voice of the first bank is playing `note`
+pub fn playing(synth: &Synth, note: u8) -> bool {
+    synth.osc_s[0].0.iter().any(|osc| osc.playing == Some(note))
+}
@@ -0,0 +1,283 @@
+use synth_rt::{
+    env::{Curve, CurveShape, Envelope, ModTarget, Stage, ADSR},
+    osc::{filter_sweep, TablePosition},
+    synth::{OscType, Param, Synth},
+    wav_table::{WavTable, FRAME_SIZE},
+};
+
+const RATE: u32 = 1_000;
+
+fn curve(shape: CurveShape) -> Curve {
+    Curve { shape, amount: 1.0 }
+}
+
+/// the level half way through a 0.1 second attack
+fn half_attack(shape: CurveShape) -> f32 {
+    let mut env = ADSR::new(RATE);
+    env.set_atk(0.1);
+    env.set_curve(Stage::Attack, curve(shape));
+    env.press();
+
+    (0..50).map(|_| env.get_samnple()).last().unwrap()
+}
+
+/// the level half way through a 0.1 second release from full. (release times are scaled by a
+/// quarter)
+fn half_release(shape: CurveShape) -> f32 {
+    let mut env = ADSR::new(RATE);
+    env.set_atk(0.0);
+    env.set_sus(1.0);
+    env.set_release(0.4);
+    env.set_curve(Stage::Release, curve(shape));
+    env.press();
+    (0..10).for_each(|_| _ = env.get_samnple());
+    env.release();
+
+    (0..50).map(|_| env.get_samnple()).last().unwrap()
+}
+
+#[test]
+fn curves_bend_the_stages() {
+    assert!((half_attack(CurveShape::Linear) - 0.5).abs() < 0.01);
+    assert!(half_attack(CurveShape::Exponential) < 0.2);
+    assert!(half_attack(CurveShape::Logarithmic) > 0.8);
+
+    assert!((half_release(CurveShape::Linear) - 0.5).abs() < 0.01);
+    // an exponential release falls fast then tails off
+    assert!(half_release(CurveShape::Exponential) < 0.2);
+    assert!(half_release(CurveShape::Logarithmic) > 0.8);
+}
+
+#[test]
+fn curves_keep_the_stage_times() {
+    for shape in CurveShape::ALL {
+        let mut env = ADSR::new(RATE);
+        env.set_atk(0.1);
+        env.set_curve(Stage::Attack, curve(shape));
+        env.press();
+
+        // the decay starts once the attack is over
+        let attack = (0..RATE)
+            .take_while(|_| {
+                env.get_samnple();
+                env.phase == 1
+            })
+            .count();
+        assert!((99..=101).contains(&attack), "{shape} => {attack}");
+    }
+}
+
+#[test]
+fn release_starts_from_the_current_level() {
+    let mut env = ADSR::new(RATE);
+    env.set_atk(0.1);
+    env.set_sus(0.8);
+    env.set_release(0.1);
+    env.press();
+
+    // let go a third of the way through the attack, well below the sustain level
+    let level = (0..33).map(|_| env.get_samnple()).last().unwrap();
+    env.release();
+
+    let first = env.get_samnple();
+    assert!(first <= level && level - first < 0.02, "{level} => {first}");
+
+    let released: Vec<f32> = (0..RATE).map(|_| env.get_samnple()).collect();
+    assert!(released.windows(2).all(|w| w[1] <= w[0]));
+    assert_eq!(released[100], 0.0);
+}
+
+#[test]
+fn retriggering_starts_from_the_current_level() {
+    let mut env = ADSR::new(RATE);
+    env.set_atk(0.1);
+    env.set_release(0.5);
+    env.press();
+    (0..200).for_each(|_| _ = env.get_samnple());
+    env.release();
+
+    let level = (0..100).map(|_| env.get_samnple()).last().unwrap();
+    env.press();
+
+    let first = env.get_samnple();
+    assert!(first >= level && first - level < 0.02, "{level} => {first}");
+}
+
+/// a synth with only the first bank, playing a sine with no effects. every envelope jumps
+/// straight to its sustain level.
+fn one_sine() -> Synth {
+    let mut synth = Synth::default();
+    synth.chorus.turn_power_on(false);
+    synth.reverb.turn_power_on(false);
+    synth.set_muted(1, true);
+    synth.set_muted(2, true);
+    synth.osc_type[0] = (OscType::Sin, 1.0);
+    synth.set_leslie_speed(0.0);
+
+    for envelope in Envelope::ALL {
+        synth.edit_envelope(envelope, |env| {
+            env.set_atk(0.0);
+            env.set_decay(0.01);
+            env.set_sus(1.0);
+        });
+    }
+
+    synth
+}
+
+/// the loudness of a note once its envelopes have settled
+fn sustained_rms(synth: &mut Synth) -> f32 {
+    synth.play(60, 127);
+    (0..4_800).for_each(|_| _ = synth.get_sample());
+    let samples: Vec<f32> = (0..4_800).map(|_| synth.get_sample()).collect();
+
+    (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+/// `one_sine` playing a table that's silent in its first frame and a square in its last, from
+/// `position`
+fn one_table(position: TablePosition) -> Synth {
+    let samples: Vec<f32> = (0..FRAME_SIZE)
+        .map(|_| 0.0)
+        .chain((0..FRAME_SIZE).map(|i| if i < FRAME_SIZE / 2 { 0.5 } else { -0.5 }))
+        .collect();
+
+    let mut synth = one_sine();
+    synth.set_wav_tables(vec![WavTable::from_samples("fade-in", &samples)]);
+    synth.wait_for_tables();
+    // every voice mixes all three tables, so the other two are silenced
+    synth.osc_type = [
+        (OscType::Custom(0), 1.0),
+        (OscType::Sin, 0.0),
+        (OscType::Sin, 0.0),
+    ];
+    synth.table_position[0] = position;
+
+    synth
+}
+
+/// how far through its cycle the note moves every sample, once its envelopes have settled
+fn sustained_step(synth: &mut Synth) -> f32 {
+    synth.play(57, 127);
+    (0..4_800).for_each(|_| _ = synth.get_sample());
+
+    let before = synth.osc_s[0].0[0].phase(0);
+    synth.get_sample();
+
+    (synth.osc_s[0].0[0].phase(0) - before).rem_euclid(1.0)
+}
+
+#[test]
+fn filter_sweep_is_bipolar() {
+    assert_eq!(filter_sweep(0.25, 1.0), 0.25);
+    assert_eq!(filter_sweep(0.25, 0.0), 1.0);
+    assert_eq!(filter_sweep(0.25, -1.0), 0.75);
+    assert_eq!(filter_sweep(0.0, -0.5), 1.0);
+    assert_eq!(filter_sweep(1.0, -0.5), 0.5);
+}
+
+#[test]
+fn filter_envelope_is_separate_from_the_amp_envelope() {
+    let mut open = one_sine();
+    let mut closed = one_sine();
+    // the filter closes after its decay while the amp envelope holds
+    closed.set_param(Param::FilterSustain, 0.05);
+
+    let (open, closed) = (sustained_rms(&mut open), sustained_rms(&mut closed));
+    assert!(closed < open * 0.5, "{open} => {closed}");
+
+    // a negative amount closes the filter as the envelope rises instead
+    let mut inverted = one_sine();
+    inverted.set_param(Param::FilterEnvAmount, 0.0);
+    let inverted = sustained_rms(&mut inverted);
+    assert!(inverted < open * 0.5, "{open} => {inverted}");
+
+    let mut off = one_sine();
+    off.set_param(Param::FilterEnvAmount, 0.5);
+    off.set_param(Param::FilterSustain, 0.0);
+    let off = sustained_rms(&mut off);
+    assert!((off - open).abs() < open * 0.05, "{open} => {off}");
+}
+
+#[test]
+fn mod_envelope_moves_the_pitch() {
+    let plain = sustained_step(&mut one_sine());
+
+    let mut up = one_sine();
+    up.set_mod_target(ModTarget::Pitch);
+    up.set_param(Param::ModEnvAmount, 1.0);
+    let up = sustained_step(&mut up);
+
+    let mut down = one_sine();
+    down.set_mod_target(ModTarget::Pitch);
+    down.set_param(Param::ModEnvAmount, 0.0);
+    let down = sustained_step(&mut down);
+
+    // an octave either way
+    assert!((up / plain - 2.0).abs() < 0.001, "{plain} => {up}");
+    assert!((down / plain - 0.5).abs() < 0.001, "{plain} => {down}");
+}
+
+#[test]
+fn mod_envelope_moves_the_cutoff() {
+    let mut open = one_sine();
+    open.set_cutoff(0.1);
+    let open = sustained_rms(&mut open);
+
+    let mut closed = one_sine();
+    closed.set_cutoff(0.1);
+    closed.set_mod_target(ModTarget::Cutoff);
+    closed.set_mod_amount(-1.0);
+    let closed = sustained_rms(&mut closed);
+
+    assert!(closed < open * 0.5, "{open} => {closed}");
+}
+
+#[test]
+fn old_patches_sweep_the_filter_with_the_amp_envelope() {
+    let mut synth = Synth::default();
+    synth.set_atk(0.2);
+    synth.set_sus(0.3);
+
+    let mut patch = synth.snapshot_patch();
+    patch.filter_envelope = None;
+    patch.mod_envelope = None;
+
+    let mut loaded = Synth::default();
+    loaded.apply_patch(&patch);
+
+    let loaded = loaded.snapshot_patch();
+    let filter = loaded.filter_envelope.unwrap();
+    assert_eq!(filter.envelope, patch.envelope);
+    assert_eq!(filter.amount, 1.0);
+    assert_eq!(loaded.mod_envelope.unwrap().amount, 0.0);
+}
+
+#[test]
+fn mod_envelope_sweeps_the_table_position() {
+    let swept = TablePosition {
+        position: 0.0,
+        lfo: 0.0,
+        env: 1.0,
+    };
+    let open = sustained_rms(&mut one_table(swept));
+
+    // the amp envelope holds while the mod envelope falls back to the silent frame
+    let mut closed = one_table(swept);
+    closed.set_param(Param::ModSustain, 0.0);
+    let closed = sustained_rms(&mut closed);
+
+    assert!(closed < open * 0.05, "{open} => {closed}");
+}
+
+#[test]
+fn mod_envelope_moves_the_table_position() {
+    let silent = sustained_rms(&mut one_table(TablePosition::default()));
+
+    let mut moved = one_table(TablePosition::default());
+    moved.set_mod_target(ModTarget::TablePosition);
+    moved.set_mod_amount(1.0);
+    let moved = sustained_rms(&mut moved);
+
+    assert!(silent < moved * 0.05, "{silent} => {moved}");
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use common::playing;
+use std::{sync::Arc, thread::spawn};
+use synth_rt::{
+    event::Event,
+    synth::{Param, Synth},
+    Player, BLOCK_SIZE, CHANNELS,
+};
+
+#[test]
+fn events_are_applied_at_block_boundaries() {
+    let (mut player, synth) = Player::new(Synth::default());
+
+    // the first sample starts a block, which is `BLOCK_SIZE` interleaved frames long
+    player.next();
+    synth.send(Event::NoteOn {
+        note: 60,
+        velocity: 100,
+    });
+
+    for _ in 1..BLOCK_SIZE * CHANNELS as usize {
+        player.next();
+        assert!(!playing(&player.synth, 60));
+    }
+
+    player.next();
+    assert!(playing(&player.synth, 60));
+}
+
+#[test]
+fn edits_are_published_for_the_ui() {
+    let (mut player, synth) = Player::new(Synth::default());
+    let before = synth.state();
+
+    synth.send(Event::Param(Param::Volume, 0.2));
+    synth.edit(|synth| synth.osc_s[1].1 = 7);
+    // nothing changes until the audio thread gets to them
+    assert_eq!(synth.state(), before);
+
+    player.next();
+    let state = synth.state();
+    assert_eq!(state.volume, 0.2);
+    assert_eq!(state.oscillators[1].detune, 7);
+}
+
+#[test]
+fn notes_alone_dont_republish() {
+    let (mut player, synth) = Player::new(Synth::default());
+    let before = synth.state();
+
+    synth.send(Event::NoteOn {
+        note: 60,
+        velocity: 100,
+    });
+    player.next();
+
+    assert!(Arc::ptr_eq(&before, &synth.state()));
+}
+
+#[test]
+fn many_threads_can_send() {
+    let (mut player, synth) = Player::new(Synth::default());
+
+    let senders: Vec<_> = (0..4)
+        .map(|i| {
+            let synth = synth.clone();
+
+            spawn(move || {
+                synth.send(Event::NoteOn {
+                    note: 60 + i,
+                    velocity: 100,
+                })
+            })
+        })
+        .collect();
+
+    for sender in senders {
+        sender.join().unwrap();
+    }
+
+    player.next();
+    assert!((60..64).all(|note| playing(&player.synth, note)));
+}
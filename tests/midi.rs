@@ -0,0 +1,300 @@
+mod common;
+
+use common::{playing, temp_dir};
+use midi_control::{control_change, note_off, note_on, pitch_bend, program_change, Channel};
+use std::sync::{Arc, Mutex};
+use synth_rt::{
+    bank::Bank,
+    cc_map::{CcBinding, CcMap, Curve},
+    midi::{self, decode_hex, MidiRouter},
+    synth::{OscType, Param, Synth, VelocityCurve},
+    Player, DEFAULT_SAMPLE_RATE,
+};
+
+fn pressed(synth: &Synth, note: u8) -> bool {
+    synth.osc_s[0]
+        .0
+        .iter()
+        .any(|osc| osc.playing == Some(note) && osc.is_pressed())
+}
+
+#[test]
+fn note_on_and_off() {
+    let mut synth = Synth::default();
+    let router = MidiRouter::new(None);
+
+    router.apply(&mut synth, &note_on(Channel::Ch1, 60, 100));
+    assert!(pressed(&synth, 60));
+
+    router.apply(&mut synth, &note_off(Channel::Ch1, 60, 0));
+    assert!(playing(&synth, 60));
+    assert!(!pressed(&synth, 60));
+}
+
+#[test]
+fn zero_velocity_note_on_is_note_off() {
+    let mut synth = Synth::default();
+    let router = MidiRouter::new(None);
+
+    router.apply(&mut synth, &note_on(Channel::Ch1, 64, 100));
+    router.apply(&mut synth, &note_on(Channel::Ch1, 64, 0));
+    assert!(!pressed(&synth, 64));
+}
+
+#[test]
+fn cc_map_is_the_same_for_every_input() {
+    let mut synth = Synth::default();
+    let router = MidiRouter::new(None);
+
+    router.apply(&mut synth, &control_change(Channel::Ch1, 70, 127));
+    assert_eq!(synth.snapshot_patch().envelope.attack, 1.0);
+
+    router.apply(&mut synth, &control_change(Channel::Ch1, 7, 0));
+    assert_eq!(synth.volume, 0.0);
+
+    router.apply(&mut synth, &control_change(Channel::Ch1, 74, 127));
+    assert_eq!(synth.snapshot_patch().filter.cutoff, 1.0);
+}
+
+#[test]
+fn envelope_stages_have_default_ccs() {
+    let mut synth = Synth::default();
+    let router = MidiRouter::new(None);
+
+    router.apply(&mut synth, &control_change(Channel::Ch1, 102, 127));
+    router.apply(&mut synth, &control_change(Channel::Ch1, 108, 0));
+
+    let patch = synth.snapshot_patch();
+    assert_eq!(patch.filter_envelope.unwrap().envelope.attack, 1.0);
+    assert_eq!(patch.mod_envelope.unwrap().envelope.sustain, 0.0);
+    // the amp envelope is left alone
+    assert_ne!(patch.envelope.attack, 1.0);
+}
+
+#[test]
+fn cc_map_channel_and_range() {
+    let mut synth = Synth::default();
+    let mut binding = CcBinding::new(20, Some(2), Param::Volume);
+    binding.min = 0.2;
+    binding.max = 0.6;
+    binding.curve = Curve::Exponential;
+    let router = MidiRouter::with_cc_map(
+        CcMap {
+            bindings: vec![binding],
+            path: None,
+        },
+        None,
+    );
+
+    // wrong channel
+    router.apply(&mut synth, &control_change(Channel::Ch1, 20, 0));
+    assert_ne!(synth.volume, 0.2);
+
+    router.apply(&mut synth, &control_change(Channel::Ch2, 20, 0));
+    assert_eq!(synth.volume, 0.2);
+
+    router.apply(&mut synth, &control_change(Channel::Ch2, 20, 127));
+    assert_eq!(synth.volume, 0.6);
+}
+
+#[test]
+fn midi_learn_binds_the_next_cc() {
+    let mut synth = Synth::default();
+    let router = MidiRouter::new(None);
+
+    router.learn(Some(Param::Volume));
+    router.apply(&mut synth, &control_change(Channel::Ch3, 30, 0));
+    assert_eq!(router.learning(), None);
+    assert_eq!(synth.volume, 0.0);
+
+    router.apply(&mut synth, &control_change(Channel::Ch3, 30, 127));
+    assert_eq!(synth.volume, 1.0);
+
+    // the old volume binding is replaced
+    router.apply(&mut synth, &control_change(Channel::Ch1, 7, 0));
+    assert_eq!(synth.volume, 1.0);
+}
+
+/// the loudest sample of the first tenth of a second of a note
+fn peak(synth: &mut Synth, velocity: u8) -> f32 {
+    let router = MidiRouter::new(None);
+    router.apply(synth, &note_on(Channel::Ch1, 60, velocity));
+
+    (0..4_800)
+        .map(|_| synth.get_sample().abs())
+        .fold(0.0, f32::max)
+}
+
+#[test]
+fn velocity_scales_level() {
+    let loud = peak(&mut Synth::default(), 127);
+    let soft = peak(&mut Synth::default(), 32);
+    assert!(soft < loud * 0.5, "soft: {soft}, loud: {loud}");
+
+    let mut synth = Synth::default();
+    synth.velocity_curve = VelocityCurve::Fixed;
+    assert_eq!(peak(&mut synth, 32), loud);
+}
+
+#[test]
+fn midi_learn_replaces_a_default_binding_on_the_same_cc() {
+    let mut synth = Synth::default();
+    let router = MidiRouter::new(None);
+    synth.set_volume(0.5);
+
+    // 74 is bound to the cutoff on every channel by default
+    router.learn(Some(Param::Volume));
+    router.apply(&mut synth, &control_change(Channel::Ch2, 74, 127));
+    assert_eq!(synth.volume, 1.0);
+
+    let cutoff = synth.snapshot_patch().filter.cutoff;
+    router.apply(&mut synth, &control_change(Channel::Ch2, 74, 0));
+    assert_eq!(synth.volume, 0.0);
+    assert_eq!(synth.snapshot_patch().filter.cutoff, cutoff);
+
+    // and the cutoff is no longer on 74 on any other channel either
+    router.apply(&mut synth, &control_change(Channel::Ch1, 74, 0));
+    assert_eq!(synth.snapshot_patch().filter.cutoff, cutoff);
+}
+
+#[test]
+fn sustain_pedal_holds_notes() {
+    let mut synth = Synth::default();
+    let router = MidiRouter::new(None);
+
+    router.apply(&mut synth, &control_change(Channel::Ch1, 64, 127));
+    router.apply(&mut synth, &note_on(Channel::Ch1, 60, 100));
+    router.apply(&mut synth, &note_off(Channel::Ch1, 60, 0));
+    assert!(pressed(&synth, 60));
+
+    router.apply(&mut synth, &control_change(Channel::Ch1, 64, 0));
+    assert!(!pressed(&synth, 60));
+}
+
+#[test]
+fn sostenuto_only_holds_notes_down_when_pressed() {
+    let mut synth = Synth::default();
+    let router = MidiRouter::new(None);
+
+    router.apply(&mut synth, &note_on(Channel::Ch1, 48, 100));
+    router.apply(&mut synth, &control_change(Channel::Ch1, 66, 127));
+    router.apply(&mut synth, &note_on(Channel::Ch1, 60, 100));
+    router.apply(&mut synth, &note_off(Channel::Ch1, 48, 0));
+    router.apply(&mut synth, &note_off(Channel::Ch1, 60, 0));
+    assert!(pressed(&synth, 48));
+    assert!(!pressed(&synth, 60));
+
+    router.apply(&mut synth, &control_change(Channel::Ch1, 66, 0));
+    assert!(!pressed(&synth, 48));
+}
+
+#[test]
+fn soft_pedal_lowers_level() {
+    let loud = peak(&mut Synth::default(), 127);
+
+    let mut synth = Synth::default();
+    MidiRouter::new(None).apply(&mut synth, &control_change(Channel::Ch1, 67, 127));
+    assert!(peak(&mut synth, 127) < loud);
+}
+
+#[test]
+fn all_notes_off() {
+    let mut synth = Synth::default();
+    let router = MidiRouter::new(None);
+
+    router.apply(&mut synth, &note_on(Channel::Ch1, 60, 100));
+    router.apply(&mut synth, &note_on(Channel::Ch1, 67, 100));
+    router.apply(&mut synth, &control_change(Channel::Ch1, 123, 0));
+    assert!(!pressed(&synth, 60));
+    assert!(!pressed(&synth, 67));
+}
+
+/// a synth playing a plain sine with the effects off, so its pitch can be read from the output
+fn sine_synth() -> Synth {
+    let mut synth = Synth::default();
+    synth.osc_type = [(OscType::Sin, 1.0); 3];
+    synth.overtones.iter_mut().for_each(|ot| ot.volume = if ot.overtone == 1.0 { 1.0 } else { 0.0 });
+    synth.set_overtones();
+    // no vibrato, so only the bend moves the pitch
+    synth.set_leslie_speed(0.0);
+    synth.chorus.turn_power_on(false);
+    synth.reverb.power = false;
+
+    synth
+}
+
+/// how many cycles the synth plays in a second, once the note has settled
+fn cycles(synth: &mut Synth) -> usize {
+    (0..DEFAULT_SAMPLE_RATE / 10).for_each(|_| _ = synth.get_sample());
+    let samples: Vec<f32> = (0..DEFAULT_SAMPLE_RATE).map(|_| synth.get_sample()).collect();
+
+    samples
+        .windows(2)
+        .filter(|pair| pair[0] < 0.0 && pair[1] >= 0.0)
+        .count()
+}
+
+#[test]
+fn pitch_bend_bends_playing_notes() {
+    let mut synth = sine_synth();
+    let router = MidiRouter::new(None);
+
+    router.apply(&mut synth, &note_on(Channel::Ch1, 60, 100));
+    let unbent = synth.snapshot_patch();
+    let before = cycles(&mut synth);
+
+    // the bend range is three semitones either way
+    router.apply(&mut synth, &pitch_bend(Channel::Ch1, 0x3FFF));
+    let after = cycles(&mut synth);
+    let ratio = after as f32 / before as f32;
+    let expected = 2.0_f32.powf(midi::pitch_bend(0x7F, 0x7F) * 3.0 / 12.0);
+    assert!((ratio - expected).abs() < 0.01, "{before} => {after}");
+
+    // bending doesn't change any parameters
+    assert_eq!(synth.snapshot_patch(), unbent);
+    assert!(pressed(&synth, 60));
+
+    // and letting go of the wheel goes back to the note
+    router.apply(&mut synth, &pitch_bend(Channel::Ch1, 0x2000));
+    assert!(cycles(&mut synth).abs_diff(before) <= 1);
+}
+
+#[test]
+fn program_change_loads_preset() {
+    let dir = temp_dir("midi-bank");
+
+    let mut patch = Synth::default().snapshot_patch();
+    patch.volume = 0.25;
+
+    let mut bank = Bank::load(&dir);
+    bank.select(3);
+    bank.save_as("quiet", patch).unwrap();
+    bank.select(0);
+
+    let bank = Arc::new(Mutex::new(bank));
+    let router = MidiRouter::new(Some(bank.clone()));
+    let mut synth = Synth::default();
+
+    router.apply(&mut synth, &program_change(Channel::Ch1, 3));
+    assert_eq!(synth.volume, 0.25);
+    assert_eq!(bank.lock().unwrap().current, 3);
+
+    // empty slots leave the sound alone
+    router.apply(&mut synth, &program_change(Channel::Ch1, 4));
+    assert_eq!(synth.volume, 0.25);
+}
+
+#[test]
+fn serial_hex_decodes_to_midi() {
+    let bytes = decode_hex("903C64\r\n").unwrap();
+    assert_eq!(bytes, [0x90, 0x3C, 0x64]);
+    assert_eq!(decode_hex("903C64\n").unwrap(), bytes);
+
+    // a line cut short is an error, not a panic
+    assert!(decode_hex("903C6\n").is_err());
+
+    let (mut player, synth) = Player::new(Synth::default());
+    MidiRouter::new(None).handle_bytes(&synth, &bytes);
+    player.next();
+    assert!(pressed(&player.synth, 0x3C));
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use common::temp_dir;
+use hound::WavReader;
+use midly::{
+    num::u4, Format, Header, MetaMessage, MidiMessage, Smf, Timing, TrackEvent, TrackEventKind,
+};
+use std::path::{Path, PathBuf};
+use synth_rt::{
+    midi::MidiRouter,
+    render::{load_midi_file, render_midi_file, BitDepth},
+    synth::Synth,
+    DEFAULT_SAMPLE_RATE,
+};
+
+/// writes a MIDI file that holds middle C for a beat, (half a second at the default tempo)
+fn one_note(dir: &Path) -> PathBuf {
+    let event = |delta: u32, message| TrackEvent {
+        delta: delta.into(),
+        kind: TrackEventKind::Midi {
+            channel: u4::new(0),
+            message,
+        },
+    };
+    let track = vec![
+        event(
+            0,
+            MidiMessage::NoteOn {
+                key: 60.into(),
+                vel: 100.into(),
+            },
+        ),
+        event(
+            480,
+            MidiMessage::NoteOff {
+                key: 60.into(),
+                vel: 0.into(),
+            },
+        ),
+        TrackEvent {
+            delta: 0.into(),
+            kind: TrackEventKind::Meta(MetaMessage::EndOfTrack),
+        },
+    ];
+    let smf = Smf {
+        header: Header::new(Format::SingleTrack, Timing::Metrical(480.into())),
+        tracks: vec![track],
+    };
+
+    let path = dir.join("one_note.mid");
+    smf.save(&path).unwrap();
+
+    path
+}
+
+#[test]
+fn events_are_timed_in_samples() {
+    let events =
+        load_midi_file(one_note(&temp_dir("midi-render-timed")), DEFAULT_SAMPLE_RATE).unwrap();
+
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].sample, 0);
+    assert_eq!(events[1].sample, DEFAULT_SAMPLE_RATE as u64 / 2);
+}
+
+#[test]
+fn renders_a_midi_file_to_wav() {
+    let dir = temp_dir("midi-render-wav");
+    let wav = dir.join("one_note.wav");
+    render_midi_file(
+        &mut Synth::default(),
+        &MidiRouter::new(None),
+        one_note(&dir),
+        &wav,
+        BitDepth::Int16,
+        0.5,
+    )
+    .unwrap();
+
+    let mut reader = WavReader::open(&wav).unwrap();
+    let spec = reader.spec();
+    assert_eq!(spec.sample_rate, DEFAULT_SAMPLE_RATE);
+    assert_eq!(spec.bits_per_sample, 16);
+
+    // the note, then the tail
+    assert_eq!(reader.duration(), DEFAULT_SAMPLE_RATE);
+
+    let peak = reader
+        .samples::<i16>()
+        .map(|sample| sample.unwrap().unsigned_abs())
+        .max()
+        .unwrap();
+    assert!(peak > 100, "{peak}");
+}
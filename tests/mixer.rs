@@ -0,0 +1,100 @@
+use synth_rt::{synth::Synth, voice::VoiceMode};
+
+fn playing(synth: &Synth, bank: usize) -> usize {
+    synth.osc_s[bank]
+        .0
+        .iter()
+        .filter(|osc| osc.playing.is_some())
+        .count()
+}
+
+fn render(synth: &mut Synth) -> Vec<f32> {
+    let mut out = vec![0.0; 4_800];
+    synth.render(&mut out);
+
+    out
+}
+
+#[test]
+fn muted_banks_take_no_voices() {
+    let mut synth = Synth::default();
+    synth.set_muted(0, true);
+    synth.play(60, 100);
+    synth.play(64, 100);
+
+    assert_eq!(playing(&synth, 0), 0);
+    assert_eq!(playing(&synth, 1), 2);
+    assert_eq!(playing(&synth, 2), 2);
+
+    synth.stop(60);
+    assert!(synth.osc_s[1].0.iter().any(|osc| !osc.is_pressed()));
+}
+
+#[test]
+fn stealing_follows_the_first_bank_that_is_on() {
+    let mut synth = Synth::default();
+    synth.set_polyphony(2);
+    synth.set_muted(0, true);
+
+    for note in [60, 64, 67] {
+        synth.play(note, 100);
+    }
+
+    assert_eq!(playing(&synth, 0), 0);
+    assert!(synth.osc_s[1].0.iter().any(|osc| osc.playing == Some(67)));
+    assert_eq!(
+        synth.osc_s[1]
+            .0
+            .iter()
+            .map(|osc| osc.playing)
+            .collect::<Vec<_>>(),
+        synth.osc_s[2]
+            .0
+            .iter()
+            .map(|osc| osc.playing)
+            .collect::<Vec<_>>(),
+    );
+}
+
+#[test]
+fn solo_plays_only_the_soloed_banks() {
+    let mut soloed = Synth::default();
+    soloed.set_solo(2, true);
+    soloed.play(60, 100);
+
+    let mut muted = Synth::default();
+    muted.set_muted(0, true);
+    muted.set_muted(1, true);
+    muted.play(60, 100);
+
+    assert_eq!(playing(&soloed, 0) + playing(&soloed, 1), 0);
+    assert_eq!(render(&mut soloed), render(&mut muted));
+
+    // a muted bank stays off even when it's soloed
+    soloed.set_muted(2, true);
+    assert_eq!(playing(&soloed, 2), 0);
+    assert!(!soloed.bank_on(0) && !soloed.bank_on(2));
+}
+
+#[test]
+fn muting_a_playing_bank_silences_it() {
+    let mut synth = Synth::default();
+    synth.play(60, 100);
+    synth.set_muted(1, true);
+
+    assert_eq!(playing(&synth, 1), 0);
+    assert_eq!(playing(&synth, 0), 1);
+}
+
+#[test]
+fn nothing_plays_with_every_bank_off() {
+    for mode in [VoiceMode::Poly, VoiceMode::Mono] {
+        let mut synth = Synth::default();
+        synth.set_voice_mode(mode);
+        synth.muted = [true; 3];
+        synth.play(60, 100);
+        synth.stop(60);
+
+        assert_eq!((0..3).map(|bank| playing(&synth, bank)).sum::<usize>(), 0);
+    }
+}
@@ -0,0 +1,75 @@
+use synth_rt::{
+    osc::RatioMode,
+    synth::{OscType, Synth},
+};
+
+#[test]
+fn semitone_mode_snaps_and_steps() {
+    let fifth = 2.0_f64.powf(7.0 / 12.0);
+
+    assert!((RatioMode::Semitone.snap(1.5) - fifth).abs() < 1e-9);
+    assert!((RatioMode::Semitone.step(fifth, true) - 2.0_f64.powf(8.0 / 12.0)).abs() < 1e-9);
+    assert!((RatioMode::Semitone.step(1.0, false) - 2.0_f64.powf(-1.0 / 12.0)).abs() < 1e-9);
+}
+
+#[test]
+fn harmonic_mode_counts_through_subharmonics() {
+    assert_eq!(RatioMode::Harmonic.snap(2.7), 3.0);
+    assert_eq!(RatioMode::Harmonic.snap(0.3), 1.0 / 3.0);
+
+    let mut ratio = 3.0;
+    let mut ratios = Vec::new();
+
+    for _ in 0..4 {
+        ratio = RatioMode::Harmonic.step(ratio, false);
+        ratios.push(ratio);
+    }
+
+    assert_eq!(ratios, [2.0, 1.0, 0.5, 1.0 / 3.0]);
+    assert_eq!(RatioMode::Harmonic.step(0.5, true), 1.0);
+}
+
+#[test]
+fn free_mode_is_clamped() {
+    assert_eq!(RatioMode::Free.snap(1.2345), 1.2345);
+    assert_eq!(RatioMode::Free.snap(1000.0), 32.0);
+}
+
+#[test]
+fn rebuild_swaps_in_new_tables() {
+    let mut synth = Synth::default();
+    let before = synth.wave_tables.load_full();
+
+    synth.overtones[2].overtone = 2.0;
+    synth.set_overtones();
+
+    // the old tables keep playing until the builder thread is done
+    synth.get_sample();
+    synth.wait_for_tables();
+
+    let after = synth.wave_tables.load_full();
+    assert_ne!(before.table(OscType::Sin), after.table(OscType::Sin));
+}
+
+#[test]
+fn only_the_last_rebuild_matters() {
+    let mut synth = Synth::default();
+
+    for ratio in 1..=20 {
+        synth.overtones[2].overtone = ratio as f64;
+        synth.set_overtones();
+    }
+
+    synth.wait_for_tables();
+    let built = synth.wave_tables.load_full();
+
+    let mut expected = Synth::default();
+    expected.overtones[2].overtone = 20.0;
+    expected.set_overtones();
+    expected.wait_for_tables();
+
+    assert_eq!(
+        built.table(OscType::Saw),
+        expected.wave_tables.load().table(OscType::Saw)
+    );
+}
@@ -0,0 +1,154 @@
+mod common;
+
+use common::temp_dir;
+use synth_rt::{
+    env::{Curve, CurveShape, Envelope, ModTarget, Stage},
+    osc::{RatioMode, TablePosition, Unison},
+    patch::{Patch, Wave},
+    synth::{OscType, Param, Synth, VelocityCurve},
+    voice::{GlideMode, NotePriority, StealPolicy, VoiceMode},
+    wav_table::WavTable,
+};
+
+/// saves the synth's patch to a file, loads it back and applies it to a new synth with the same
+/// wave tables. nothing may be lost on the way.
+fn round_trip(synth: &Synth, name: &str) -> Patch {
+    let path = temp_dir(name).join("patch.toml");
+    let patch = synth.snapshot_patch();
+    patch.save(&path).unwrap();
+
+    let loaded = Patch::load(&path).unwrap();
+    assert_eq!(loaded, patch);
+
+    let mut applied = Synth::default();
+    applied.set_wav_tables(synth.wave_tables.load().custom.clone());
+    applied.apply_patch(&loaded);
+    assert_eq!(applied.snapshot_patch(), patch);
+
+    applied.snapshot_patch()
+}
+
+#[test]
+fn every_parameter_survives_a_save_and_load() {
+    let mut synth = Synth::default();
+    synth.set_volume(0.5);
+    synth.osc_type[1] = (OscType::Saw, 0.25);
+    synth.set_wav_tables(vec![WavTable::from_samples("ramp", &[0.0, 0.5, 1.0, -0.5])]);
+    synth.osc_type[2].0 = OscType::Custom(0);
+    synth.osc_s[2].1 = -7;
+    synth.pan = [-0.5, 0.0, 0.75];
+    synth.spread[1] = 0.4;
+    synth.set_muted(1, true);
+    synth.set_solo(2, true);
+    synth.set_octave(0, -1);
+    synth.set_fine_tune(2, 7.5);
+    let unison = Unison {
+        voices: 2,
+        detune: 12.5,
+        width: 0.25,
+    };
+    synth.set_unison(1, unison);
+    synth.table_position[2] = TablePosition {
+        position: 0.5,
+        lfo: 0.25,
+        env: -0.25,
+    };
+    synth.overtones[4].volume = 0.125;
+    synth.overtones[3].mode = RatioMode::Harmonic;
+    synth.overtones[3].overtone = 7.0;
+    synth.set_atk(0.25);
+    synth.set_decay(0.5);
+    synth.set_sus(0.75);
+    synth.set_release(0.5);
+    let exp = Curve {
+        shape: CurveShape::Exponential,
+        amount: 0.25,
+    };
+    synth.set_env_curve(Envelope::Amp, Stage::Release, exp);
+    synth.set_param(Param::FilterAttack, 0.3);
+    synth.set_filter_env_amount(-0.5);
+    synth.set_param(Param::ModRelease, 0.7);
+    synth.set_mod_target(ModTarget::TablePosition);
+    synth.set_mod_amount(0.25);
+    synth.set_cutoff(0.5);
+    synth.set_resonace(0.25);
+    synth.lfo.set_frequency(3.0);
+    synth.lfo.set_volume(0.5);
+    synth.chorus.turn_power_on(false);
+    synth.chorus.set_volume(0.25);
+    synth.chorus.set_speed(0.25);
+    synth.chorus.set_width(0.8);
+    synth.reverb.turn_power_on(false);
+    synth.reverb.set_gain(0.25);
+    synth.reverb.set_decay(0.75);
+    synth.velocity_curve = VelocityCurve::Exponential;
+    synth.set_velocity_cutoff(0.5);
+    synth.set_polyphony(4);
+    synth.voices.policy = StealPolicy::Quietest;
+    synth.set_voice_mode(VoiceMode::Legato);
+    synth.note_priority = NotePriority::High;
+    synth.glide = 0.25;
+    synth.glide_mode = GlideMode::ConstantRate;
+
+    let patch = round_trip(&synth, "patch-every-parameter");
+    assert_eq!(patch.volume, 0.5);
+    assert_eq!(patch.oscillators[1].wave, Wave::Saw);
+    assert_eq!(patch.oscillators[1].volume, 0.25);
+    assert_eq!(patch.oscillators[2].wave, Wave::Custom("ramp".into()));
+    assert_eq!(patch.oscillators[2].detune, -7);
+    assert_eq!(
+        patch.oscillators.each_ref().map(|osc| osc.pan),
+        [-0.5, 0.0, 0.75]
+    );
+    assert_eq!(patch.oscillators[1].spread, 0.4);
+    assert!(patch.oscillators[1].muted);
+    assert!(patch.oscillators[2].solo);
+    assert_eq!(patch.oscillators[0].octave, -1);
+    assert_eq!(patch.oscillators[2].fine_tune, 7.5);
+    assert_eq!(patch.oscillators[1].unison, unison);
+    assert_eq!(patch.oscillators[2].position.position, 0.5);
+    assert_eq!(patch.oscillators[2].position.lfo, 0.25);
+    assert_eq!(patch.oscillators[2].position.env, -0.25);
+    assert_eq!(patch.overtones[4].volume, 0.125);
+    assert_eq!(patch.overtones[3].mode, RatioMode::Harmonic);
+    assert_eq!(patch.overtones[3].overtone, 7.0);
+    assert_eq!(patch.envelope.attack, 0.25);
+    assert_eq!(patch.envelope.decay, 0.5);
+    assert_eq!(patch.envelope.sustain, 0.75);
+    assert_eq!(patch.envelope.release, 0.5);
+    assert_eq!(patch.envelope.attack_curve, Curve::default());
+    assert_eq!(patch.envelope.release_curve, exp);
+    let filter = patch.filter_envelope.as_ref().unwrap();
+    assert_eq!(filter.envelope.attack, 0.3);
+    assert_eq!(filter.amount, -0.5);
+    let modulation = patch.mod_envelope.as_ref().unwrap();
+    assert!((modulation.envelope.release - 0.7).abs() < 1e-6);
+    assert_eq!(modulation.target, ModTarget::TablePosition);
+    assert_eq!(modulation.amount, 0.25);
+    assert_eq!(patch.filter.cutoff, 0.5);
+    assert_eq!(patch.filter.resonance, 0.25);
+    assert_eq!(patch.lfo.frequency, 3.0);
+    assert_eq!(patch.lfo.volume, 0.5);
+    assert!(!patch.chorus.power);
+    assert_eq!(patch.chorus.volume, 0.25);
+    assert_eq!(patch.chorus.speed, 0.25);
+    assert_eq!(patch.chorus.width, 0.8);
+    assert!(!patch.reverb.power);
+    assert_eq!(patch.reverb.gain, 0.25);
+    assert_eq!(patch.reverb.decay, 0.75);
+    assert_eq!(patch.velocity.curve, VelocityCurve::Exponential);
+    assert_eq!(patch.velocity.cutoff, 0.5);
+    assert_eq!(patch.voices.polyphony, 4);
+    assert_eq!(patch.voices.stealing, StealPolicy::Quietest);
+    assert_eq!(patch.voices.mode, VoiceMode::Legato);
+    assert_eq!(patch.voices.priority, NotePriority::High);
+    assert_eq!(patch.voices.glide, 0.25);
+    assert_eq!(patch.voices.glide_mode, GlideMode::ConstantRate);
+}
+
+#[test]
+fn loading_a_missing_patch_fails() {
+    let path = temp_dir("patch-missing").join("patch.toml");
+
+    assert!(Patch::load(path).is_err());
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use common::temp_dir;
+use hound::{SampleFormat, WavReader};
+use synth_rt::{record::Recorder, CHANNELS, DEFAULT_SAMPLE_RATE};
+
+#[test]
+fn records_a_burst_to_wav() {
+    let dir = temp_dir("record-burst");
+    let (mut recorder, mut tap) = Recorder::new(&dir, DEFAULT_SAMPLE_RATE);
+
+    // nothing is kept before the recording starts
+    tap.push(1.0, 1.0);
+    assert!(!recorder.is_recording());
+
+    let file = recorder.start().unwrap();
+    assert!(recorder.is_recording());
+    assert!(recorder.elapsed().is_some());
+    assert_eq!(file.parent().unwrap(), dir);
+
+    // synth-rt-YYYY-MM-DD_HH-MM-SS.wav
+    let name = file.file_name().unwrap().to_str().unwrap();
+    assert!(
+        name.starts_with("synth-rt-") && name.ends_with(".wav"),
+        "{name}"
+    );
+    assert_eq!(
+        name.len(),
+        "synth-rt-2024-01-01_00-00-00.wav".len(),
+        "{name}"
+    );
+
+    let burst: Vec<f32> = (0..480 * CHANNELS as usize)
+        .map(|i| i as f32 / 1_000.0 - 0.5)
+        .collect();
+    burst
+        .chunks(2)
+        .for_each(|frame| tap.push(frame[0], frame[1]));
+
+    assert_eq!(recorder.stop().unwrap(), file);
+    assert!(!recorder.is_recording());
+    assert!(recorder.elapsed().is_none());
+
+    // nothing is kept after it stops
+    tap.push(1.0, 1.0);
+
+    let mut reader = WavReader::open(&file).unwrap();
+    let spec = reader.spec();
+    assert_eq!(spec.channels, CHANNELS);
+    assert_eq!(spec.sample_rate, DEFAULT_SAMPLE_RATE);
+    assert_eq!(spec.bits_per_sample, 32);
+    assert_eq!(spec.sample_format, SampleFormat::Float);
+    assert_eq!(reader.duration(), 480);
+
+    let samples: Vec<f32> = reader.samples::<f32>().map(Result::unwrap).collect();
+    assert_eq!(samples, burst);
+}
+
+#[test]
+fn a_full_ring_drops_whole_frames() {
+    let dir = temp_dir("record-full");
+    // a ring buffer that only holds two frames
+    let (mut recorder, mut tap) = Recorder::new(&dir, 1);
+    let file = recorder.start().unwrap();
+
+    // the writer thread might drain the ring in between, but never half a frame
+    for i in 0..1_000 {
+        tap.push(i as f32, -(i as f32));
+    }
+
+    recorder.stop().unwrap();
+
+    let mut reader = WavReader::open(&file).unwrap();
+    let samples: Vec<f32> = reader.samples::<f32>().map(Result::unwrap).collect();
+    assert!(!samples.is_empty());
+    assert!(samples.chunks(2).all(|frame| frame[0] == -frame[1]));
+}
+
+#[test]
+fn start_and_stop_are_checked() {
+    let (mut recorder, _tap) = Recorder::new(temp_dir("record-checked"), DEFAULT_SAMPLE_RATE);
+
+    assert!(recorder.stop().is_err());
+
+    recorder.start().unwrap();
+    assert!(recorder.start().is_err());
+    recorder.stop().unwrap();
+
+    // the recorder can be used again
+    recorder.start().unwrap();
+    recorder.stop().unwrap();
+}
@@ -0,0 +1,61 @@
+use std::f32::consts::FRAC_1_SQRT_2;
+use synth_rt::synth::{Synth, MAX_BLOCK};
+
+fn chord() -> Synth {
+    let mut synth = Synth::default();
+    synth.chorus.turn_power_on(true);
+    synth.reverb.turn_power_on(true);
+
+    for note in [48, 55, 60, 64] {
+        synth.play(note, 100);
+    }
+
+    synth
+}
+
+#[test]
+fn blocks_match_single_samples() {
+    let len = MAX_BLOCK * 3 + 17;
+
+    let mut one_at_a_time = chord();
+    let expected: Vec<f32> = (0..len).map(|_| one_at_a_time.get_sample()).collect();
+
+    let mut blocks = chord();
+    let mut rendered = vec![0.0; len];
+    blocks.render(&mut rendered);
+
+    for (i, (a, b)) in expected.iter().zip(rendered.iter()).enumerate() {
+        assert!((a - b).abs() < 1e-5, "sample {i}: {a} != {b}");
+    }
+}
+
+#[test]
+fn mono_is_the_stereo_mix() {
+    let mut mono = chord();
+    let expected: Vec<f32> = (0..100).map(|_| mono.get_sample()).collect();
+
+    let mut stereo = chord();
+    let mut frames = vec![0.0; 200];
+    stereo.render_stereo(&mut frames);
+
+    for (frame, expected) in frames.chunks(2).zip(expected) {
+        assert!(((frame[0] + frame[1]) * FRAC_1_SQRT_2 - expected).abs() < 1e-5);
+    }
+}
+
+#[test]
+fn notes_that_end_mid_block_stop() {
+    let mut synth = Synth::default();
+    synth.set_release(0.001);
+    synth.play(60, 100);
+    synth.render(&mut [0.0; MAX_BLOCK]);
+    synth.stop(60);
+
+    let mut out = [0.0; MAX_BLOCK];
+    synth.render(&mut out);
+
+    assert!(synth
+        .osc_s
+        .iter()
+        .all(|(osc_s, _)| osc_s.iter().all(|osc| osc.playing.is_none())));
+}
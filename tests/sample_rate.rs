@@ -0,0 +1,75 @@
+use std::{f32::consts::PI, sync::Arc};
+use synth_rt::{
+    env::ADSR,
+    lfo::LFO,
+    mipmap::MipMap,
+    osc::{TableMix, TablePosition, WavetableOscillator},
+    synth::{Synth, WaveTable, WAVE_TABLE_SIZE},
+};
+
+const RATE: u32 = 44_100;
+
+/// how many samples between the first two rising zero crossings
+fn period(mut next: impl FnMut() -> f32) -> usize {
+    let mut last = next();
+    let mut crossings = Vec::new();
+
+    for i in 1..10_000 {
+        let sample = next();
+
+        if last < 0.0 && sample >= 0.0 {
+            crossings.push(i);
+        }
+
+        if crossings.len() == 2 {
+            break;
+        }
+
+        last = sample;
+    }
+
+    crossings[1] - crossings[0]
+}
+
+#[test]
+fn oscillator_stays_in_tune() {
+    let sine: WaveTable = (0..WAVE_TABLE_SIZE)
+        .map(|i| (2.0 * PI * i as f32 / WAVE_TABLE_SIZE as f32).sin())
+        .collect();
+    let tables = [TableMix {
+        frames: Arc::new([MipMap::new(&sine)]),
+        volume: 1.0,
+        position: TablePosition::default(),
+    }];
+
+    let mut osc = WavetableOscillator::new(RATE);
+    osc.set_frequency(441.0);
+
+    assert_eq!(period(|| osc.get_sample(&tables, 0.0, 0.0, 0.0)), 100);
+}
+
+#[test]
+fn lfo_stays_in_time() {
+    let mut lfo = LFO::new(RATE);
+    lfo.set_frequency(441.0);
+
+    assert_eq!(period(|| lfo.get_sample()), 100);
+}
+
+#[test]
+fn envelope_times_are_in_seconds() {
+    let mut env = ADSR::new(RATE);
+    env.set_atk(0.01);
+    env.press();
+
+    let attack = (0..RATE).take_while(|_| env.get_samnple() < 1.0).count();
+    assert!((attack as i64 - 441).abs() <= 1, "{attack} samples");
+}
+
+#[test]
+fn synth_passes_its_rate_on() {
+    let synth = Synth::new(RATE);
+
+    assert_eq!(synth.sample_rate, RATE);
+    assert_eq!(synth.chorus.buff.len(), RATE as usize);
+}
@@ -0,0 +1,135 @@
+use rodio::Source;
+use synth_rt::{
+    reverb::Reverb,
+    synth::{pan_gains, voice_pan, Synth},
+    voice::VoiceMode,
+    Player,
+};
+
+/// the loudest sample of the left and right channels over `frames` frames
+fn peaks(synth: &mut Synth, frames: usize) -> (f32, f32) {
+    let mut out = vec![0.0; frames * 2];
+    synth.render_stereo(&mut out);
+
+    out.chunks(2).fold((0.0, 0.0), |(left, right), frame| {
+        (left.max(frame[0].abs()), right.max(frame[1].abs()))
+    })
+}
+
+/// a synth with no effects, so only the panning moves the sound
+fn dry() -> Synth {
+    let mut synth = Synth::default();
+    synth.chorus.turn_power_on(false);
+    synth.reverb.turn_power_on(false);
+
+    synth
+}
+
+#[test]
+fn pan_keeps_the_power_constant() {
+    for pan in [-1.0, -0.5, 0.0, 0.3, 1.0] {
+        let (left, right) = pan_gains(pan);
+        assert!((left * left + right * right - 1.0).abs() < 1e-6);
+    }
+
+    assert!(pan_gains(-1.0).1.abs() < 1e-6);
+    assert!(pan_gains(1.0).0.abs() < 1e-6);
+    assert_eq!(pan_gains(0.0).0, pan_gains(0.0).1);
+}
+
+#[test]
+fn hard_panned_banks_play_on_one_side() {
+    let mut synth = dry();
+    synth.pan = [-1.0; 3];
+    synth.play(60, 100);
+
+    let (left, right) = peaks(&mut synth, 4_800);
+    assert!(left > 0.01);
+    assert!(right < 1e-4, "{right}");
+}
+
+#[test]
+fn spread_fans_voices_out() {
+    assert_eq!(voice_pan(0.0, 1.0, 0, 4), -1.0);
+    assert_eq!(voice_pan(0.0, 1.0, 3, 4), 1.0);
+    assert_eq!(voice_pan(0.5, 0.0, 3, 4), 0.5);
+    assert_eq!(voice_pan(0.5, 1.0, 3, 4), 1.0);
+    assert_eq!(voice_pan(0.5, 1.0, 0, 1), 0.5);
+
+    // the first voice is on the far left
+    let mut synth = dry();
+    synth.set_polyphony(2);
+    synth.spread = [1.0; 3];
+    synth.play(60, 100);
+
+    let (left, right) = peaks(&mut synth, 4_800);
+    assert!(left > 0.01);
+    assert!(right < 1e-4, "{right}");
+
+    // but not in mono mode, where only the first voice plays
+    synth.set_voice_mode(VoiceMode::Mono);
+    synth.play(60, 100);
+    let (left, right) = peaks(&mut synth, 4_800);
+    assert!((left - right).abs() < 1e-4);
+}
+
+#[test]
+fn effects_widen_a_centered_sound() {
+    let differs = |synth: &mut Synth| {
+        synth.play(60, 100);
+        let mut out = vec![0.0; 19_200];
+        synth.render_stereo(&mut out);
+
+        out.chunks(2)
+            .map(|frame| (frame[0] - frame[1]).abs())
+            .fold(0.0, f32::max)
+    };
+
+    let mut chorus = dry();
+    chorus.chorus.turn_power_on(true);
+    chorus.chorus.set_width(0.0);
+    assert!(differs(&mut chorus) < 1e-6);
+
+    let mut chorus = dry();
+    chorus.chorus.turn_power_on(true);
+    chorus.chorus.set_width(1.0);
+    assert!(differs(&mut chorus) > 0.01);
+
+    let mut reverb = dry();
+    reverb.reverb.turn_power_on(true);
+    assert!(differs(&mut reverb) > 0.01);
+}
+
+#[test]
+fn reverb_delay_is_in_milliseconds() {
+    // the same impulse on both channels, the right plate hears it 10 ms later
+    for sample_rate in [22_050, 48_000, 96_000] {
+        let mut reverb = Reverb::new(sample_rate);
+        reverb.turn_power_on(true);
+
+        // long enough for the impulse to come out of both plates
+        let len = 8_000;
+        let mut impulse = vec![0.0; len];
+        impulse[0] = 1.0;
+        let (mut left, mut right) = (vec![0.0; len], vec![0.0; len]);
+        reverb.process(&impulse, &impulse, &mut left, &mut right);
+
+        let heard = |out: &[f32]| out.iter().position(|sample| *sample != 0.0).unwrap();
+        assert_eq!(heard(&right) - heard(&left), sample_rate as usize / 100);
+    }
+}
+
+#[test]
+fn player_plays_interleaved_frames() {
+    let mut expected = Synth::default();
+    expected.play(60, 100);
+    let mut frames = vec![0.0; 256];
+    expected.render_stereo(&mut frames);
+
+    let mut synth = Synth::default();
+    synth.play(60, 100);
+    let (player, _handle) = Player::new(synth);
+
+    assert_eq!(player.channels(), 2);
+    assert_eq!(player.take(256).collect::<Vec<_>>(), frames);
+}
@@ -0,0 +1,86 @@
+use synth_rt::{synth::Synth, voice::VoiceMode};
+
+/// the frequency each bank plays the first voice at
+fn frequencies(synth: &Synth) -> [f32; 3] {
+    [0, 1, 2].map(|bank| synth.osc_s[bank].0[0].frequency())
+}
+
+fn close(a: f32, b: f32) -> bool {
+    (a / b - 1.0).abs() < 1e-4
+}
+
+#[test]
+fn fine_tune_is_in_cents() {
+    let mut synth = Synth::default();
+    synth.set_fine_tune(1, 50.0);
+    synth.set_fine_tune(2, -100.0);
+    synth.play(69, 100);
+
+    let [a, b, c] = frequencies(&synth);
+    assert!(close(b, a * 2.0_f32.powf(50.0 / 1_200.0)));
+    // a whole semitone down is the same as the coarse detune
+    assert!(close(c, a / 2.0_f32.powf(1.0 / 12.0)));
+}
+
+#[test]
+fn octaves_double_the_frequency() {
+    let mut synth = Synth::default();
+    synth.set_octave(1, 1);
+    synth.set_octave(2, -2);
+    synth.osc_s[2].1 = 7;
+    synth.play(60, 100);
+
+    let [a, b, c] = frequencies(&synth);
+    assert!(close(b, a * 2.0));
+    assert!(close(c, a / 4.0 * 2.0_f32.powf(7.0 / 12.0)));
+
+    // and they're clamped
+    synth.set_octave(0, 9);
+    assert_eq!(synth.octave[0], 3);
+}
+
+#[test]
+fn held_notes_follow_the_tuning() {
+    let mut synth = Synth::default();
+    synth.play(60, 100);
+    let [before, ..] = frequencies(&synth);
+
+    synth.set_fine_tune(0, 25.0);
+    let [after, ..] = frequencies(&synth);
+    assert!(close(after, before * 2.0_f32.powf(25.0 / 1_200.0)));
+
+    // new voices keep the tuning too
+    synth.set_polyphony(12);
+    assert!(synth.osc_s[0]
+        .0
+        .iter()
+        .all(|osc| close(osc.tuning(), 2.0_f32.powf(25.0 / 1_200.0))));
+}
+
+#[test]
+fn glides_land_on_the_tuned_note() {
+    let mut synth = Synth::default();
+    synth.set_voice_mode(VoiceMode::Mono);
+    synth.glide = 0.01;
+    synth.set_octave(0, 1);
+    synth.play(60, 100);
+    synth.play(72, 100);
+    synth.render(&mut [0.0; 4_800]);
+
+    let mut plain = Synth::default();
+    plain.play(84, 100);
+
+    assert!(close(frequencies(&synth)[0], frequencies(&plain)[0]));
+}
+
+#[test]
+fn applying_a_patch_retunes_the_voices() {
+    let mut synth = Synth::default();
+    synth.set_octave(0, -1);
+    synth.set_fine_tune(2, 7.5);
+
+    let mut loaded = Synth::default();
+    loaded.apply_patch(&synth.snapshot_patch());
+    assert_eq!(loaded.osc_s[0].0[3].tuning(), synth.osc_s[0].0[3].tuning());
+    assert_eq!(loaded.osc_s[2].0[3].tuning(), synth.osc_s[2].0[3].tuning());
+}
@@ -0,0 +1,141 @@
+use synth_rt::{
+    osc::{Unison, MAX_UNISON, UNISON_BUDGET},
+    synth::Synth,
+    voice::MAX_POLYPHONY,
+};
+
+/// a synth with only the first bank and no effects
+fn one_bank(unison: Unison) -> Synth {
+    let mut synth = Synth::default();
+    synth.chorus.turn_power_on(false);
+    synth.reverb.turn_power_on(false);
+    synth.set_muted(1, true);
+    synth.set_muted(2, true);
+    synth.set_unison(0, unison);
+
+    synth
+}
+
+fn frames(synth: &mut Synth, len: usize) -> Vec<f32> {
+    let mut out = vec![0.0; len * 2];
+    synth.render_stereo(&mut out);
+
+    out
+}
+
+fn rms(samples: &[f32]) -> f32 {
+    (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+#[test]
+fn copies_are_spread_across_the_detune() {
+    let unison = Unison {
+        voices: 3,
+        detune: 40.0,
+        width: 0.0,
+    };
+    assert_eq!(unison.position(0), -1.0);
+    assert_eq!(unison.position(1), 0.0);
+    assert_eq!(unison.position(2), 1.0);
+    assert_eq!(Unison::default().position(0), 0.0);
+
+    let mut plain = one_bank(Unison::default());
+    let mut stacked = one_bank(unison);
+    plain.play(60, 100);
+    stacked.play(60, 100);
+
+    assert_ne!(frames(&mut plain, 4_800), frames(&mut stacked, 4_800));
+}
+
+#[test]
+fn copies_start_at_random_phases() {
+    let mut synth = one_bank(Unison {
+        voices: 4,
+        ..Default::default()
+    });
+    let phases = |synth: &Synth| [0, 1, 2, 3].map(|i| synth.osc_s[0].0[0].phase(i));
+
+    synth.play(60, 100);
+    let first = phases(&synth);
+    assert!(first.iter().any(|phase| *phase != first[0]));
+
+    // the same note played again on the same voice starts somewhere else
+    synth.osc_s[0].0[0].reset();
+    synth.play(60, 100);
+    assert_ne!(phases(&synth), first);
+}
+
+#[test]
+fn width_spreads_copies_across_the_stereo_field() {
+    let differs = |width| {
+        let mut synth = one_bank(Unison {
+            voices: 4,
+            detune: 30.0,
+            width,
+        });
+        synth.play(60, 100);
+
+        frames(&mut synth, 4_800)
+            .chunks(2)
+            .map(|frame| (frame[0] - frame[1]).abs())
+            .fold(0.0, f32::max)
+    };
+
+    assert_eq!(differs(0.0), 0.0);
+    assert!(differs(1.0) > 0.01);
+}
+
+#[test]
+fn stacking_keeps_the_level() {
+    let level = |voices| {
+        let mut synth = one_bank(Unison {
+            voices,
+            detune: 30.0,
+            width: 0.5,
+        });
+        synth.play(48, 100);
+
+        rms(&frames(&mut synth, 9_600))
+    };
+
+    let (one, eight) = (level(1), level(8));
+    assert!(eight > one * 0.5 && eight < one * 2.0, "{one} {eight}");
+}
+
+#[test]
+fn unison_stays_inside_the_budget() {
+    let mut synth = Synth::default();
+    synth.set_unison(
+        0,
+        Unison {
+            voices: 100,
+            ..Default::default()
+        },
+    );
+    assert_eq!(synth.unison[0].voices, synth.max_unison().min(MAX_UNISON));
+
+    // more voices leave less room for unison copies
+    synth.set_polyphony(MAX_POLYPHONY);
+    let max = (UNISON_BUDGET / MAX_POLYPHONY).clamp(1, MAX_UNISON);
+    assert_eq!(synth.max_unison(), max);
+    assert!(synth.unison[0].voices <= max);
+    assert!(synth.osc_s[0]
+        .0
+        .iter()
+        .all(|osc| osc.unison().voices <= max));
+}
+
+#[test]
+fn applying_a_patch_sets_unison_on_the_voices() {
+    let mut synth = Synth::default();
+    let unison = Unison {
+        voices: 2,
+        detune: 12.5,
+        width: 0.25,
+    };
+    synth.set_unison(1, unison);
+
+    let mut loaded = Synth::default();
+    loaded.apply_patch(&synth.snapshot_patch());
+    assert_eq!(loaded.osc_s[1].0[5].unison(), unison);
+}
@@ -0,0 +1,189 @@
+use synth_rt::{
+    synth::Synth,
+    voice::{GlideMode, NotePriority, StealPolicy, VoiceMode},
+};
+
+fn voices_playing(synth: &Synth, note: u8) -> usize {
+    synth.osc_s[0]
+        .0
+        .iter()
+        .filter(|osc| osc.playing == Some(note))
+        .count()
+}
+
+/// runs the synth long enough for a stolen voice to fade out
+fn run(synth: &mut Synth, secs: f32) {
+    for _ in 0..(secs * 48_000.0) as usize {
+        synth.get_sample();
+    }
+}
+
+#[test]
+fn steals_oldest_voice() {
+    let mut synth = Synth::default();
+    synth.set_polyphony(2);
+
+    synth.play(60, 100);
+    synth.play(62, 100);
+    synth.play(64, 100);
+    run(&mut synth, 0.01);
+
+    assert_eq!(voices_playing(&synth, 60), 0);
+    assert_eq!(voices_playing(&synth, 62), 1);
+    assert_eq!(voices_playing(&synth, 64), 1);
+}
+
+#[test]
+fn steals_lowest_note() {
+    let mut synth = Synth::default();
+    synth.set_polyphony(2);
+    synth.voices.policy = StealPolicy::LowestPriority;
+
+    synth.play(72, 100);
+    synth.play(48, 100);
+    synth.play(60, 100);
+
+    assert_eq!(voices_playing(&synth, 72), 1);
+    assert_eq!(voices_playing(&synth, 48), 0);
+}
+
+#[test]
+fn steals_quietest_voice() {
+    let mut synth = Synth::default();
+    synth.set_polyphony(2);
+    synth.voices.policy = StealPolicy::Quietest;
+
+    synth.play(60, 100);
+    // let the first note decay to the sustain level
+    run(&mut synth, 0.5);
+    synth.play(62, 100);
+    run(&mut synth, 0.01);
+    synth.play(64, 100);
+
+    assert_eq!(voices_playing(&synth, 60), 1);
+    assert_eq!(voices_playing(&synth, 62), 0);
+}
+
+#[test]
+fn releasing_voices_are_stolen_first() {
+    let mut synth = Synth::default();
+    synth.set_polyphony(2);
+
+    synth.play(60, 100);
+    synth.play(62, 100);
+    synth.stop(62);
+    synth.play(64, 100);
+
+    assert_eq!(voices_playing(&synth, 60), 1);
+    assert_eq!(voices_playing(&synth, 62), 0);
+}
+
+#[test]
+fn retriggers_releasing_voice() {
+    let mut synth = Synth::default();
+
+    synth.play(60, 100);
+    synth.stop(60);
+    run(&mut synth, 0.01);
+    synth.play(60, 100);
+
+    assert_eq!(voices_playing(&synth, 60), 1);
+    assert!(synth.osc_s[0].0.iter().any(|osc| osc.is_pressed()));
+}
+
+#[test]
+fn polyphony_is_clamped() {
+    let mut synth = Synth::default();
+
+    synth.set_polyphony(0);
+    assert_eq!(synth.voices.polyphony(), 1);
+    assert_eq!(synth.osc_s[2].0.len(), 1);
+
+    synth.set_polyphony(16);
+    assert_eq!(synth.voices.polyphony(), 16);
+    assert_eq!(synth.osc_s[1].0.len(), 16);
+}
+
+#[test]
+fn mono_returns_to_held_note() {
+    let mut synth = Synth::default();
+    synth.set_voice_mode(VoiceMode::Mono);
+
+    synth.play(60, 100);
+    synth.play(64, 100);
+    assert_eq!(voices_playing(&synth, 60), 0);
+    assert_eq!(voices_playing(&synth, 64), 1);
+
+    synth.stop(64);
+    assert_eq!(voices_playing(&synth, 60), 1);
+    assert!(synth.osc_s[0].0[0].is_pressed());
+
+    synth.stop(60);
+    assert!(!synth.osc_s[0].0[0].is_pressed());
+}
+
+#[test]
+fn mono_low_note_priority() {
+    let mut synth = Synth::default();
+    synth.set_voice_mode(VoiceMode::Mono);
+    synth.note_priority = NotePriority::Low;
+
+    synth.play(60, 100);
+    synth.play(64, 100);
+    assert_eq!(voices_playing(&synth, 60), 1);
+
+    synth.play(55, 100);
+    assert_eq!(voices_playing(&synth, 55), 1);
+}
+
+#[test]
+fn legato_doesnt_retrigger() {
+    for (mode, retriggered) in [(VoiceMode::Mono, true), (VoiceMode::Legato, false)] {
+        let mut synth = Synth::default();
+        synth.set_voice_mode(mode);
+        synth.set_atk(0.001);
+
+        synth.play(60, 100);
+        // let the envelope decay to the sustain level
+        run(&mut synth, 0.5);
+        synth.play(62, 100);
+        run(&mut synth, 0.005);
+
+        assert_eq!(synth.osc_s[0].0[0].level() > 0.9, retriggered, "{mode:?}");
+    }
+}
+
+#[test]
+fn glide_slides_between_notes() {
+    let mut synth = Synth::default();
+    synth.set_voice_mode(VoiceMode::Legato);
+    synth.glide = 0.1;
+
+    synth.play(48, 100);
+    let from = synth.osc_s[0].0[0].frequency();
+    synth.play(60, 100);
+    run(&mut synth, 0.05);
+    let halfway = synth.osc_s[0].0[0].frequency();
+    run(&mut synth, 0.06);
+    let to = synth.osc_s[0].0[0].frequency();
+
+    assert!((to / from - 2.0).abs() < 0.001);
+    // the glide is even in pitch, so halfway is half an octave up
+    assert!((halfway / from - 2.0_f32.sqrt()).abs() < 0.01);
+}
+
+#[test]
+fn constant_rate_glide_scales_with_interval() {
+    let mut synth = Synth::default();
+    synth.set_voice_mode(VoiceMode::Legato);
+    synth.glide = 0.1;
+    synth.glide_mode = GlideMode::ConstantRate;
+
+    // two octaves take twice the glide time
+    synth.play(36, 100);
+    let from = synth.osc_s[0].0[0].frequency();
+    synth.play(60, 100);
+    run(&mut synth, 0.1);
+
+    assert!((synth.osc_s[0].0[0].frequency() / from - 2.0).abs() < 0.01);
+}
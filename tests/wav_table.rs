@@ -0,0 +1,170 @@
+mod common;
+
+use common::temp_dir;
+use hound::{SampleFormat, WavSpec, WavWriter};
+use std::path::{Path, PathBuf};
+use synth_rt::{
+    osc::{TableMix, TablePosition, WavetableOscillator},
+    patch::Wave,
+    synth::{OscType, Synth, WAVE_TABLE_SIZE},
+    wav_table::{WavTable, FRAME_SIZE},
+    DEFAULT_SAMPLE_RATE,
+};
+
+fn write_wav(dir: &Path, name: &str, samples: impl Iterator<Item = f32>) -> PathBuf {
+    let path = dir.join(name);
+
+    let spec = WavSpec {
+        channels: 1,
+        sample_rate: 44_100,
+        bits_per_sample: 16,
+        sample_format: SampleFormat::Int,
+    };
+    let mut writer = WavWriter::create(&path, spec).unwrap();
+
+    for sample in samples {
+        writer
+            .write_sample((sample * i16::MAX as f32) as i16)
+            .unwrap();
+    }
+
+    writer.finalize().unwrap();
+
+    path
+}
+
+fn square(len: usize) -> impl Iterator<Item = f32> {
+    (0..len).map(move |i| if i < len / 2 { 0.5 } else { -0.5 })
+}
+
+#[test]
+fn loads_single_cycle() {
+    let dir = temp_dir("wav-table-single");
+    let table = WavTable::load(write_wav(&dir, "square.wav", square(600))).unwrap();
+    assert_eq!(&*table.name, "square");
+    assert_eq!(table.frames.len(), 1);
+
+    let cycle = table.frames[0].table();
+    assert_eq!(cycle.len(), WAVE_TABLE_SIZE);
+    assert!((cycle[WAVE_TABLE_SIZE / 4] - 0.5).abs() < 0.05);
+    assert!((cycle[WAVE_TABLE_SIZE * 3 / 4] + 0.5).abs() < 0.05);
+}
+
+#[test]
+fn loads_multi_frame() {
+    // three frames, each one a louder square
+    let samples = (1..=3).flat_map(|frame| square(FRAME_SIZE).map(move |s| s * frame as f32 / 3.0));
+    let table = WavTable::load(write_wav(
+        &temp_dir("wav-table-multi"),
+        "frames.wav",
+        samples,
+    ))
+    .unwrap();
+    assert_eq!(table.frames.len(), 3);
+
+    let peak = |frame: usize| table.frames[frame].table()[WAVE_TABLE_SIZE / 4];
+    assert!(peak(0) < peak(1) && peak(1) < peak(2));
+}
+
+#[test]
+fn custom_osc_type_plays_and_saves() {
+    let mut synth = Synth::default();
+    synth.set_wav_tables(vec![WavTable::from_samples(
+        "square",
+        &square(600).collect::<Vec<_>>(),
+    )]);
+    synth.osc_type = [(OscType::Custom(0), 1.0); 3];
+
+    // the custom table is kept when the overtones change
+    synth.set_overtones();
+    synth.wait_for_tables();
+    assert_eq!(synth.wave_tables.load().custom.len(), 1);
+
+    synth.play(60, 127);
+    let peak = (0..4_800)
+        .map(|_| synth.get_sample().abs())
+        .fold(0.0, f32::max);
+    assert!(peak > 0.01);
+
+    // saved by name
+    let patch = synth.snapshot_patch();
+    assert_eq!(
+        patch.oscillators[0].wave,
+        Wave::Custom("square".into())
+    );
+}
+
+#[test]
+fn patches_find_their_table_by_name() {
+    let dir = temp_dir("wav-table-by-name");
+    write_wav(&dir, "square.wav", square(600));
+
+    let mut synth = Synth::default();
+    synth.set_wav_tables(WavTable::load_dir(&dir));
+    synth.osc_type[0].0 = OscType::Custom(0);
+    let patch = synth.snapshot_patch();
+
+    // a new file sorted before it moves the table's id
+    write_wav(&dir, "a-saw.wav", (0..600).map(|i| i as f32 / 600.0 - 0.5));
+    let mut synth = Synth::default();
+    synth.set_wav_tables(WavTable::load_dir(&dir));
+    synth.apply_patch(&patch);
+    assert_eq!(synth.osc_type[0].0, OscType::Custom(1));
+    assert_eq!(synth.snapshot_patch(), patch);
+
+    // and without the file the oscillator keeps its wave instead of playing something else
+    let mut synth = Synth::default();
+    synth.osc_type[0].0 = OscType::Tri;
+    synth.apply_patch(&patch);
+    assert_eq!(synth.osc_type[0].0, OscType::Tri);
+    assert_eq!(synth.snapshot_patch().volume, patch.volume);
+}
+
+/// the loudest sample of a frame mix played at `position`
+fn peak_at(table: &WavTable, position: TablePosition, lfo: f32, env: f32) -> f32 {
+    let tables = [TableMix {
+        frames: table.frames.clone(),
+        volume: 1.0,
+        position,
+    }];
+    let mut osc = WavetableOscillator::new(DEFAULT_SAMPLE_RATE);
+    osc.set_frequency(100.0);
+
+    (0..1_000)
+        .map(|_| osc.get_sample(&tables, lfo, env, 0.0).abs())
+        .fold(0.0, f32::max)
+}
+
+#[test]
+fn position_morphs_between_frames() {
+    let samples: Vec<f32> = (1..=3)
+        .flat_map(|frame| square(FRAME_SIZE).map(move |s| s * frame as f32 / 3.0))
+        .collect();
+    let table = WavTable::from_samples("frames", &samples);
+
+    let at = |position| {
+        peak_at(
+            &table,
+            TablePosition {
+                position,
+                ..Default::default()
+            },
+            0.0,
+            0.0,
+        )
+    };
+
+    assert!(at(0.0) < at(0.25));
+    assert!(at(0.25) < at(0.5));
+    // half way between the first and second frames
+    assert!((at(0.25) - (at(0.0) + at(0.5)) / 2.0).abs() < 0.02);
+
+    // the envelope and LFO move the position
+    let swept = TablePosition {
+        position: 0.0,
+        lfo: 0.5,
+        env: 0.5,
+    };
+    assert!((peak_at(&table, swept, 1.0, 1.0) - at(1.0)).abs() < 0.001);
+    assert!((peak_at(&table, swept, -1.0, 1.0) - at(0.0)).abs() < 0.001);
+}